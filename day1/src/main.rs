@@ -1,8 +1,44 @@
-fn main() {
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+/*
+    Usage: day1 [--json] [--bucket-size <n>]
+
+    After both parts, prints the depth profile, as JSON with `--json`. The
+    histogram groups depths into buckets of `n`, 100 unless given.
+*/
+fn main() -> Result<()> {
+    let mut json = false;
+    let mut bucket_size = 100;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--json" => json = true,
+            "--bucket-size" => bucket_size = value()?.parse()?,
+            _ => bail!("unknown argument `{}`", arg),
+        }
+    }
+    if bucket_size == 0 {
+        bail!("the bucket size must be at least 1");
+    }
+
     let input = include_str!("input");
     let depths: Vec<usize> = input.lines().map(|l| l.parse().unwrap()).collect();
     println!("Part 1: {}", find_result_1(&depths));
     println!("Part 2: {}", find_result_2(&depths));
+
+    let profile = DepthProfile::new(&depths, bucket_size);
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&profile)?),
+        false => print!("{}", profile),
+    }
+    Ok(())
 }
 
 fn find_result_1(depths: &[usize]) -> usize {
@@ -19,7 +55,151 @@ fn find_result_2(depths: &[usize]) -> usize {
 }
 
 fn count_increments(input: &[usize]) -> usize {
-    input.windows(2).filter(|win| win[0] < win[1]).count()
+    pairs(input).filter(|(a, b)| a < b).count()
+}
+
+// each neighbouring pair of readings, in order
+fn pairs(input: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    input.windows(2).map(|win| (win[0], win[1]))
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct DepthProfile {
+    longest_increasing_run: Run,
+    largest_drop: Option<Change>,
+    largest_rise: Option<Change>,
+    histogram: Vec<Bucket>,
+    plateaus: Vec<Run>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct Change {
+    // index of the reading the change starts from
+    index: usize,
+    amount: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct Bucket {
+    from: usize,
+    to: usize,
+    count: usize,
+}
+
+impl DepthProfile {
+    fn new(depths: &[usize], bucket_size: usize) -> DepthProfile {
+        DepthProfile {
+            longest_increasing_run: longest_increasing_run(depths),
+            largest_drop: largest_change(depths, |a, b| a.checked_sub(b)),
+            largest_rise: largest_change(depths, |a, b| b.checked_sub(a)),
+            histogram: histogram(depths, bucket_size),
+            plateaus: plateaus(depths),
+        }
+    }
+}
+
+impl fmt::Display for DepthProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let run = self.longest_increasing_run;
+        writeln!(
+            f,
+            "Longest increasing run: {} readings from index {}",
+            run.len, run.start
+        )?;
+        for (name, change) in [("drop", self.largest_drop), ("rise", self.largest_rise)] {
+            match change {
+                Some(c) => writeln!(f, "Largest {}: {} at index {}", name, c.amount, c.index)?,
+                None => writeln!(f, "Largest {}: none", name)?,
+            }
+        }
+        writeln!(f, "Histogram:")?;
+        for bucket in self.histogram.iter() {
+            writeln!(f, "  {:>6}..{:<6} {}", bucket.from, bucket.to, bucket.count)?;
+        }
+        writeln!(f, "Plateaus: {}", self.plateaus.len())?;
+        for plateau in self.plateaus.iter() {
+            writeln!(f, "  {} readings from index {}", plateau.len, plateau.start)?;
+        }
+        Ok(())
+    }
+}
+
+fn longest_increasing_run(depths: &[usize]) -> Run {
+    let mut longest = Run {
+        start: 0,
+        len: depths.len().min(1),
+    };
+    let mut current = longest;
+    for (idx, (a, b)) in pairs(depths).enumerate() {
+        match a < b {
+            true => current.len += 1,
+            false => {
+                current = Run {
+                    start: idx + 1,
+                    len: 1,
+                }
+            }
+        }
+        if current.len > longest.len {
+            longest = current;
+        }
+    }
+    longest
+}
+
+fn largest_change<F>(depths: &[usize], change: F) -> Option<Change>
+where
+    F: Fn(usize, usize) -> Option<usize>,
+{
+    pairs(depths)
+        .enumerate()
+        .filter_map(|(index, (a, b))| change(a, b).map(|amount| Change { index, amount }))
+        .filter(|c| c.amount > 0)
+        // keep the earliest of equally large changes
+        .fold(None, |best: Option<Change>, c| match best {
+            Some(b) if b.amount >= c.amount => Some(b),
+            _ => Some(c),
+        })
+}
+
+fn histogram(depths: &[usize], bucket_size: usize) -> Vec<Bucket> {
+    assert!(bucket_size > 0, "bucket size must be at least 1");
+    let (min, max) = match (depths.iter().min(), depths.iter().max()) {
+        (Some(&min), Some(&max)) => (min / bucket_size, max / bucket_size),
+        _ => return vec![],
+    };
+
+    let mut buckets = (min..=max)
+        .map(|b| Bucket {
+            from: b * bucket_size,
+            to: (b + 1) * bucket_size,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for depth in depths {
+        buckets[depth / bucket_size - min].count += 1;
+    }
+    buckets
+}
+
+fn plateaus(depths: &[usize]) -> Vec<Run> {
+    let mut result: Vec<Run> = vec![];
+    for (idx, (a, b)) in pairs(depths).enumerate() {
+        if a != b {
+            continue;
+        }
+        match result.last_mut() {
+            Some(run) if run.start + run.len == idx + 1 => run.len += 1,
+            _ => result.push(Run { start: idx, len: 2 }),
+        }
+    }
+    result
 }
 
 #[test]
@@ -35,3 +215,33 @@ fn part2() {
     let result = find_result_2(&vec);
     assert_eq!(5, result)
 }
+
+#[test]
+fn profile() {
+    let vec = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263, 263, 263];
+    let result = DepthProfile::new(&vec, 50);
+    assert_eq!(Run { start: 0, len: 4 }, result.longest_increasing_run);
+    assert_eq!(
+        Some(Change {
+            index: 3,
+            amount: 10
+        }),
+        result.largest_drop
+    );
+    assert_eq!(
+        Some(Change {
+            index: 5,
+            amount: 33
+        }),
+        result.largest_rise
+    );
+    assert_eq!(
+        vec![(150, 200, 1), (200, 250, 6), (250, 300, 5)],
+        result
+            .histogram
+            .iter()
+            .map(|b| (b.from, b.to, b.count))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(vec![Run { start: 9, len: 3 }], result.plateaus);
+}
@@ -0,0 +1,213 @@
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Forward(i32),
+    Back(i32),
+    Up(i32),
+    Down(i32),
+    Port(i32),
+    Starboard(i32),
+}
+
+impl Command {
+    // the command a verb names, waiting for its amount
+    fn by_verb(verb: &str) -> Option<fn(i32) -> Command> {
+        match verb {
+            "forward" => Some(Command::Forward),
+            "back" => Some(Command::Back),
+            "up" => Some(Command::Up),
+            "down" => Some(Command::Down),
+            "port" => Some(Command::Port),
+            "starboard" => Some(Command::Starboard),
+            _ => None,
+        }
+    }
+
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Command::Forward(_) => "forward",
+            Command::Back(_) => "back",
+            Command::Up(_) => "up",
+            Command::Down(_) => "down",
+            Command::Port(_) => "port",
+            Command::Starboard(_) => "starboard",
+        }
+    }
+
+    pub fn amount(&self) -> i32 {
+        match *self {
+            Command::Forward(n)
+            | Command::Back(n)
+            | Command::Up(n)
+            | Command::Down(n)
+            | Command::Port(n)
+            | Command::Starboard(n) => n,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub command: Command,
+    // the source line the command was written on, starting from 1
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+// Scripts whose repeats would unroll to more instructions than this are refused.
+const MAX_INSTRUCTIONS: usize = 1 << 22;
+
+/*
+    A route script is a list of `<verb> <amount>` commands, with `#` starting a
+    comment that runs to the end of the line. Commands can be wrapped in
+    `repeat <count> { ... }` blocks, which may be nested and span several lines.
+    The result is the flattened list of instructions in the order they run.
+*/
+pub fn parse(input: &str) -> Result<Vec<Instruction>> {
+    let mut tokens = tokenize(input).into_iter();
+    parse_block(&mut tokens, None)
+}
+
+/*
+    A fleet file names a route script for each sub:
+
+        sub alpha {
+            forward 5
+        }
+        sub bravo { down 2 forward 5 }
+*/
+pub fn parse_fleet(input: &str) -> Result<Vec<(String, Vec<Instruction>)>> {
+    let mut tokens = tokenize(input).into_iter();
+    let mut fleet: Vec<(String, Vec<Instruction>)> = vec![];
+    while let Some(token) = tokens.next() {
+        if token.text != "sub" {
+            bail!(
+                "line {}: expected `sub`, found `{}`",
+                token.line,
+                token.text
+            );
+        }
+        let name = match tokens.next() {
+            Some(t) if t.line == token.line && t.text != "{" => t.text.to_string(),
+            _ => bail!("line {}: `sub` needs a name", token.line),
+        };
+        if fleet.iter().any(|(n, _)| *n == name) {
+            bail!("line {}: sub `{}` is already defined", token.line, name);
+        }
+        match tokens.next() {
+            Some(t) if t.text == "{" => {}
+            _ => bail!("line {}: expected `{{` after `sub {}`", token.line, name),
+        }
+        fleet.push((name, parse_block(&mut tokens, Some(&token))?));
+    }
+    Ok(fleet)
+}
+
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    for (idx, line) in input.lines().enumerate() {
+        let code = line.split('#').next().unwrap();
+        let mut start = None;
+        for (pos, c) in code.char_indices() {
+            if c.is_whitespace() || c == '{' || c == '}' {
+                if let Some(s) = start.take() {
+                    tokens.push(Token {
+                        text: &code[s..pos],
+                        line: idx + 1,
+                    });
+                }
+                if !c.is_whitespace() {
+                    tokens.push(Token {
+                        text: &code[pos..pos + 1],
+                        line: idx + 1,
+                    });
+                }
+            } else if start.is_none() {
+                start = Some(pos);
+            }
+        }
+        if let Some(s) = start {
+            tokens.push(Token {
+                text: &code[s..],
+                line: idx + 1,
+            });
+        }
+    }
+    tokens
+}
+
+// `opened_by` is the token that started the block, or none at the top level
+fn parse_block<'a, I>(tokens: &mut I, opened_by: Option<&Token>) -> Result<Vec<Instruction>>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    let mut result = vec![];
+    loop {
+        let token = match tokens.next() {
+            Some(token) => token,
+            None => match opened_by {
+                Some(t) => bail!("line {}: `{}` block is never closed", t.line, t.text),
+                None => return Ok(result),
+            },
+        };
+
+        match token.text {
+            "}" => match opened_by {
+                Some(_) => return Ok(result),
+                None => bail!("line {}: unexpected `}}`", token.line),
+            },
+            "repeat" => {
+                let count = parse_number(tokens.next(), &token)?;
+                match tokens.next() {
+                    Some(t) if t.text == "{" => {}
+                    _ => bail!(
+                        "line {}: expected `{{` after `repeat {}`",
+                        token.line,
+                        count
+                    ),
+                }
+                let body = parse_block(tokens, Some(&token))?;
+                let unrolled =
+                    (body.len() as u64 * count as u64).saturating_add(result.len() as u64);
+                if unrolled > MAX_INSTRUCTIONS as u64 {
+                    bail!(
+                        "line {}: `repeat {}` unrolls to more than {} instructions",
+                        token.line,
+                        count,
+                        MAX_INSTRUCTIONS
+                    );
+                }
+                for _ in 0..count {
+                    result.extend_from_slice(&body);
+                }
+            }
+            verb => {
+                let command = Command::by_verb(verb)
+                    .ok_or_else(|| anyhow!("line {}: unknown command `{}`", token.line, verb))?;
+                let amount = parse_number(tokens.next(), &token)?;
+                result.push(Instruction {
+                    command: command(amount),
+                    line: token.line,
+                });
+            }
+        }
+    }
+}
+
+fn parse_number(token: Option<Token>, after: &Token) -> Result<i32> {
+    match token {
+        Some(t) if t.line == after.line => t
+            .text
+            .parse::<u32>()
+            .ok()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| anyhow!("line {}: `{}` is not a valid amount", t.line, t.text)),
+        _ => bail!("line {}: `{}` needs an amount", after.line, after.text),
+    }
+}
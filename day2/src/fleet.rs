@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    command::Instruction,
+    navigator::{self, Navigator},
+    Vector3,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub tick: usize,
+    pub position: Vector3,
+    pub subs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FleetReport {
+    pub collision: Option<Collision>,
+    pub finals: Vec<(String, Vector3)>,
+    pub ticks: usize,
+}
+
+/*
+    Every tick, each sub that still has instructions left runs its next one,
+    and subs that have finished hold their position. The whole fleet launches
+    from the origin, so positions are only compared from tick 1 onwards.
+*/
+pub fn simulate(fleet: &[(String, Vec<Instruction>)], model: &str) -> Result<FleetReport> {
+    let mut navigators = fleet
+        .iter()
+        .map(|_| navigator::by_name(model))
+        .collect::<Result<Vec<Box<dyn Navigator>>>>()?;
+    let ticks = fleet.iter().map(|(_, i)| i.len()).max().unwrap_or(0);
+
+    let mut collision = None;
+    for tick in 1..=ticks {
+        for ((name, instructions), navigator) in fleet.iter().zip(navigators.iter_mut()) {
+            if let Some(inst) = instructions.get(tick - 1) {
+                navigator
+                    .apply(inst.command)
+                    .map_err(|e| anyhow!("sub `{}`, line {}: {}", name, inst.line, e))?;
+            }
+        }
+
+        if collision.is_none() {
+            collision = find_collision(fleet, &navigators, tick);
+        }
+    }
+
+    Ok(FleetReport {
+        collision,
+        finals: fleet
+            .iter()
+            .zip(navigators.iter())
+            .map(|((name, _), n)| (name.clone(), n.position()))
+            .collect(),
+        ticks,
+    })
+}
+
+fn find_collision(
+    fleet: &[(String, Vec<Instruction>)],
+    navigators: &[Box<dyn Navigator>],
+    tick: usize,
+) -> Option<Collision> {
+    let mut occupied: HashMap<Vector3, Vec<usize>> = HashMap::new();
+    for (idx, navigator) in navigators.iter().enumerate() {
+        occupied.entry(navigator.position()).or_default().push(idx);
+    }
+
+    // report the group that holds the earliest sub in the fleet
+    occupied
+        .into_iter()
+        .filter(|(_, subs)| subs.len() > 1)
+        .min_by_key(|(_, subs)| subs[0])
+        .map(|(position, subs)| Collision {
+            tick,
+            position,
+            subs: subs.iter().map(|&idx| fleet[idx].0.clone()).collect(),
+        })
+}
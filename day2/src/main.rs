@@ -1,89 +1,368 @@
-fn main() {
+mod command;
+mod fleet;
+mod navigator;
+mod planner;
+mod trajectory;
+mod validation;
+
+use anyhow::{anyhow, bail, Result};
+use command::Instruction;
+use navigator::{navigate, AimNavigator, DirectNavigator};
+use trajectory::Trajectory;
+use validation::Constraints;
+
+/*
+    Usage: day2 [--model part1|part2|3d] [--csv <path>] [--svg <path>] [--at <k>]
+
+    The trajectory options record the course under the chosen model (part2 by
+    default): `--csv` and `--svg` export it, and `--at` prints the state of the
+    sub after the first `k` instructions.
+
+    Usage: day2 [--model part1|part2|3d] [--no-surfacing] [--max-depth <n>] [--max-aim <n>]
+
+    Checks the course under the chosen model against the given constraints and
+    lists every instruction that breaks one. Any exports are still written
+    before it exits with an error.
+
+    Usage: day2 [--model part1|part2|3d] --fleet <path>
+
+    Runs every sub in the fleet file in lockstep, one instruction per tick,
+    and reports where each one ends up and the first time two of them meet.
+
+    Usage: day2 --plan <horizontal>,<depth> [--max-step <n>]
+
+    Prints a shortest course that reaches the target under the part2 model,
+    keeping every amount at or below `--max-step` when given. Courses under a
+    limit are searched for, and targets too far to search are an error.
+*/
+fn main() -> Result<()> {
     let input = include_str!("input");
-    let inputs: Vec<Instruction> = input.lines().map(|l| Instruction::from(l.trim())).collect();
+    let inputs: Vec<Instruction> = command::parse(input)?;
 
-    let sub = follow_directions_1(&inputs);
-    println!("Part 1 {:?}", sub.position.x * sub.position.y);
+    let end = navigate(&mut DirectNavigator::default(), &inputs, None)?;
+    println!("Part 1 {:?}", end.x * end.y);
 
-    let sub = follow_directions_2(&inputs);
-    println!("Part 2 {:?}", sub.position.x * sub.position.y);
-}
+    let end = navigate(&mut AimNavigator::default(), &inputs, None)?;
+    println!("Part 2 {:?}", end.x * end.y);
 
-fn follow_directions_1(instructions: &[Instruction]) -> Sub {
-    let mut sub = Sub {
-        position: Vector2 { x: 0, y: 0 },
-        aim: 0,
-    };
-
-    for inst in instructions.iter() {
-        match inst.direction {
-            "forward" => sub.position.x += inst.amount,
-            "up" => sub.position.y -= inst.amount,
-            "down" => sub.position.y += inst.amount,
-            _ => panic!(),
+    let options = Options::from_args(std::env::args().skip(1))?;
+    if let Some(path) = &options.fleet {
+        let fleet = command::parse_fleet(&std::fs::read_to_string(path)?)?;
+        let report = fleet::simulate(&fleet, &options.model)?;
+        for (name, position) in report.finals.iter() {
+            println!("{}: {:?}", name, position);
+        }
+        match report.collision {
+            Some(c) => println!(
+                "First collision on tick {} at {:?}: {}",
+                c.tick,
+                c.position,
+                c.subs.join(", ")
+            ),
+            None => println!("No collisions in {} ticks", report.ticks),
         }
     }
 
-    sub
-}
+    if let Some((horizontal, depth)) = options.plan {
+        let course = planner::plan(horizontal, depth, options.max_step)?;
+        print!("{}", planner::to_script(&course));
+
+        let end = navigate(&mut AimNavigator::default(), &course, None)?;
+        println!("# {} commands, ending at {},{}", course.len(), end.x, end.y);
+    }
 
-fn follow_directions_2(instructions: &[Instruction]) -> Sub {
-    let mut sub = Sub::new();
+    if options.wants_trajectory() {
+        let mut trajectory = Trajectory::new();
+        let mut navigator = navigator::by_name(&options.model)?;
+        navigate(navigator.as_mut(), &inputs, Some(&mut trajectory))?;
 
-    for inst in instructions.iter() {
-        match inst.direction {
-            "forward" => {
-                sub.position.x += inst.amount;
-                sub.position.y += sub.aim * inst.amount;
-            }
-            "up" => sub.aim -= inst.amount,
-            "down" => sub.aim += inst.amount,
-            _ => panic!(),
+        if let Some(path) = &options.csv {
+            std::fs::write(path, trajectory.to_csv())?;
+        }
+        if let Some(path) = &options.svg {
+            std::fs::write(path, trajectory.to_svg())?;
+        }
+        if let Some(k) = options.at {
+            let waypoint = trajectory
+                .after(k)
+                .ok_or_else(|| anyhow!("the course only has {} instructions", inputs.len()))?;
+            println!("After {} instructions: {}", k, waypoint);
+        }
+
+        // after the exports, which are what's needed to see where it went wrong
+        let violations = validation::validate(&trajectory, &options.constraints);
+        for violation in violations.iter() {
+            println!("{}", violation);
+        }
+        if !violations.is_empty() {
+            bail!(
+                "the course breaks its constraints {} times",
+                violations.len()
+            );
         }
     }
 
-    sub
+    Ok(())
 }
 
-#[derive(Debug)]
-struct Sub {
-    position: Vector2,
-    aim: i32,
+#[derive(Debug, Default)]
+struct Options {
+    model: String,
+    csv: Option<String>,
+    svg: Option<String>,
+    at: Option<usize>,
+    plan: Option<(i32, i32)>,
+    max_step: Option<i32>,
+    constraints: Constraints,
+    fleet: Option<String>,
 }
 
-impl Sub {
-    fn new() -> Sub {
-        Sub {
-            position: Vector2 { x: 0, y: 0 },
-            aim: 0,
+impl Options {
+    fn from_args<I>(mut args: I) -> Result<Options>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut options = Options {
+            model: String::from("part2"),
+            ..Options::default()
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+            };
+            match arg.as_str() {
+                "--model" => options.model = value()?,
+                "--csv" => options.csv = Some(value()?),
+                "--svg" => options.svg = Some(value()?),
+                "--at" => options.at = Some(value()?.parse()?),
+                "--plan" => options.plan = Some(planner::parse_target(&value()?)?),
+                "--max-step" => options.max_step = Some(value()?.parse()?),
+                "--fleet" => options.fleet = Some(value()?),
+                "--no-surfacing" => options.constraints.no_surfacing = true,
+                "--max-depth" => options.constraints.max_depth = Some(value()?.parse()?),
+                "--max-aim" => options.constraints.max_aim = Some(value()?.parse()?),
+                _ => bail!("unknown argument `{}`", arg),
+            }
         }
+        Ok(options)
     }
-}
 
-#[derive(Debug)]
-struct Vector2 {
-    x: i32,
-    y: i32,
+    fn wants_trajectory(&self) -> bool {
+        self.csv.is_some()
+            || self.svg.is_some()
+            || self.at.is_some()
+            || !self.constraints.is_empty()
+    }
 }
 
-#[derive(Debug)]
-struct Instruction<'a> {
-    amount: i32,
-    direction: &'a str,
+// x is the horizontal position, y the depth and z the lateral offset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
 }
 
-impl<'a> Instruction<'a> {
-    fn new(amount: i32, dir: &str) -> Instruction {
-        Instruction {
-            amount,
-            direction: dir,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command::Command;
+    use validation::Breach;
+
+    const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+
+    #[test]
+    fn parts_match_example() {
+        let instructions = command::parse(EXAMPLE).unwrap();
+        let end = navigate(&mut DirectNavigator::default(), &instructions, None).unwrap();
+        assert_eq!(150, end.x * end.y);
+        let end = navigate(&mut AimNavigator::default(), &instructions, None).unwrap();
+        assert_eq!(900, end.x * end.y);
+    }
+
+    #[test]
+    fn trajectory_records_every_step() {
+        let instructions = command::parse(EXAMPLE).unwrap();
+        let mut trajectory = Trajectory::new();
+        let end = navigate(
+            &mut AimNavigator::default(),
+            &instructions,
+            Some(&mut trajectory),
+        )
+        .unwrap();
+        assert_eq!(instructions.len() + 1, trajectory.waypoints.len());
+        assert_eq!(end, trajectory.after(instructions.len()).unwrap().position);
+
+        let third = trajectory.after(3).unwrap();
+        assert_eq!(Some(3), third.line);
+        assert_eq!(Vector3 { x: 13, y: 40, z: 0 }, third.position);
+        assert_eq!(Some(5), third.aim);
+        assert_eq!("x=13, depth=40, aim=5", third.to_string());
+
+        let csv = trajectory.to_csv();
+        assert_eq!(Some("step,line,x,depth,aim"), csv.lines().next());
+        assert_eq!(Some("3,3,13,40,5"), csv.lines().nth(4));
+    }
+
+    #[test]
+    fn models_selected_by_name() {
+        let instructions = command::parse(
+            "starboard 2
+forward 3
+port 1
+forward 4",
+        )
+        .unwrap();
+        let mut navigator = navigator::by_name("3d").unwrap();
+        let end = navigate(navigator.as_mut(), &instructions, None).unwrap();
+        assert_eq!(Vector3 { x: 7, y: 0, z: 10 }, end);
+        assert_eq!(Some(&("heading", 1)), navigator.state().last());
+
+        let mut navigator = navigator::by_name("part2").unwrap();
+        let err = navigate(navigator.as_mut(), &instructions, None).unwrap_err();
+        assert_eq!(
+            "line 1: `starboard` is not supported by the part2 model",
+            err.to_string()
+        );
+        assert!(navigator::by_name("part3").is_err());
+    }
+
+    #[test]
+    fn planned_courses_reach_target() {
+        for h in -12..=12 {
+            for d in -40..=40 {
+                for limit in [None, Some(1), Some(3), Some(7)] {
+                    let course = planner::plan(h, d, limit).unwrap();
+                    let script = planner::to_script(&course);
+                    let replayed = command::parse(&script).unwrap();
+                    assert_eq!(course, replayed);
+
+                    let end = navigate(&mut AimNavigator::default(), &replayed, None).unwrap();
+                    assert_eq!((h as i64, d as i64), (end.x, end.y));
+                    if let Some(limit) = limit {
+                        assert!(course.iter().all(|i| i.command.amount() <= limit));
+                    }
+                }
+            }
         }
     }
-}
 
-impl<'a> From<&'a str> for Instruction<'a> {
-    fn from(s: &'a str) -> Self {
-        let split = s.trim().split(' ').collect::<Vec<_>>();
-        Instruction::new(split[1].parse::<i32>().unwrap(), split[0])
+    #[test]
+    fn planned_courses_are_short() {
+        let len = |h, d| planner::plan(h, d, None).unwrap().len();
+        assert_eq!(0, len(0, 0));
+        assert_eq!(1, len(15, 0));
+        assert_eq!(2, len(15, 60));
+        assert_eq!(3, len(15, 61));
+        assert_eq!(2, len(1, -7));
+        assert_eq!(3, len(0, 9));
+        assert_eq!(2, len(-4, 8));
+        assert_eq!(4, planner::plan(20, 0, Some(5)).unwrap().len());
+    }
+
+    #[test]
+    fn validation_lists_each_violation() {
+        let instructions = command::parse("down 5\nforward 4\nup 9\nforward 6\ndown 2").unwrap();
+        let mut trajectory = Trajectory::new();
+        navigate(
+            &mut AimNavigator::default(),
+            &instructions,
+            Some(&mut trajectory),
+        )
+        .unwrap();
+
+        let constraints = Constraints {
+            no_surfacing: true,
+            max_depth: Some(15),
+            max_aim: Some(4),
+        };
+        let violations = validation::validate(&trajectory, &constraints);
+        let found = violations
+            .iter()
+            .map(|v| (v.index, v.breach))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, Breach::AimTooSteep(4)),
+                (1, Breach::TooDeep(15)),
+                (1, Breach::AimTooSteep(4)),
+                (2, Breach::TooDeep(15)),
+                (3, Breach::Surfaced),
+                (4, Breach::Surfaced),
+            ],
+            found
+        );
+        assert_eq!(
+            "instruction 3 (line 4): above the surface at x=10, depth=-4, aim=-4",
+            violations[4].to_string()
+        );
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let instructions =
+            command::parse("down 2147483647\nrepeat 3 { forward 2147483647 }").unwrap();
+        let err = navigate(&mut AimNavigator::default(), &instructions, None).unwrap_err();
+        assert_eq!("line 2: arithmetic overflow", err.to_string());
+    }
+
+    #[test]
+    fn fleet_reports_first_collision() {
+        let fleet = command::parse_fleet(
+            "sub alpha {\n  forward 5\n  down 2\n  forward 1\n}\n\
+             sub bravo { down 1 forward 2 forward 3 }\n\
+             sub charlie { forward 4 repeat 3 { forward 1 } }",
+        )
+        .unwrap();
+        let report = fleet::simulate(&fleet, "part2").unwrap();
+
+        let collision = report.collision.unwrap();
+        assert_eq!(2, collision.tick);
+        assert_eq!(Vector3 { x: 5, y: 0, z: 0 }, collision.position);
+        assert_eq!(vec!["alpha", "charlie"], collision.subs);
+
+        assert_eq!(4, report.ticks);
+        assert_eq!(
+            vec![("alpha", 6, 2), ("bravo", 5, 5), ("charlie", 7, 0)],
+            report
+                .finals
+                .iter()
+                .map(|(n, p)| (n.as_str(), p.x, p.y))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(command::parse_fleet("sub alpha { forward 1 }\nsub alpha { up 1 }").is_err());
+    }
+
+    #[test]
+    fn repeat_blocks_and_comments() {
+        let script =
+            "# approach\nrepeat 2 {\n  forward 5 # cruise\n  repeat 3 { down 1 }\n}\nback 2";
+        let instructions = command::parse(script).unwrap();
+        assert_eq!(9, instructions.len());
+        assert_eq!(Command::Down(1), instructions[3].command);
+        assert_eq!(4, instructions[3].line);
+        assert_eq!(Command::Back(2), instructions[8].command);
+        assert_eq!(6, instructions[8].line);
+    }
+
+    #[test]
+    fn errors_point_at_line() {
+        let err = command::parse("forward 5\nfowrard 3").unwrap_err();
+        assert_eq!("line 2: unknown command `fowrard`", err.to_string());
+        let err = command::parse("fowrard\nforward 3").unwrap_err();
+        assert_eq!("line 1: unknown command `fowrard`", err.to_string());
+        let err = command::parse("repeat 2 {\nforward 1").unwrap_err();
+        assert_eq!("line 1: `repeat` block is never closed", err.to_string());
+        let err = command::parse_fleet("sub alpha {\nforward 1").unwrap_err();
+        assert_eq!("line 1: `sub` block is never closed", err.to_string());
+        let err = command::parse("down\n5").unwrap_err();
+        assert_eq!("line 1: `down` needs an amount", err.to_string());
+        let err = command::parse("repeat 100000 {\n  repeat 100000 { forward 1 }\n}").unwrap_err();
+        assert_eq!(
+            "line 1: `repeat 100000` unrolls to more than 4194304 instructions",
+            err.to_string()
+        );
     }
 }
@@ -0,0 +1,207 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    command::{Command, Instruction},
+    trajectory::Trajectory,
+    Vector3,
+};
+
+// A movement model: how each command changes the state of the sub.
+pub trait Navigator {
+    // where the sub is, with models that stay in a plane always at z = 0
+    fn position(&self) -> Vector3;
+    // the pitch, for models that have one
+    fn aim(&self) -> Option<i64> {
+        None
+    }
+    // everything the model keeps track of, by name, position first
+    fn state(&self) -> Vec<(&'static str, i64)>;
+    fn apply(&mut self, command: Command) -> Result<()>;
+}
+
+pub const MODELS: [&str; 3] = ["part1", "part2", "3d"];
+
+pub fn by_name(name: &str) -> Result<Box<dyn Navigator>> {
+    match name {
+        "part1" => Ok(Box::new(DirectNavigator::default())),
+        "part2" => Ok(Box::new(AimNavigator::default())),
+        "3d" => Ok(Box::new(Navigator3d::default())),
+        _ => bail!(
+            "unknown model `{}`, expected one of {}",
+            name,
+            MODELS.join(", ")
+        ),
+    }
+}
+
+// Runs the instructions and returns where they leave the sub.
+pub fn navigate(
+    navigator: &mut dyn Navigator,
+    instructions: &[Instruction],
+    mut trajectory: Option<&mut Trajectory>,
+) -> Result<Vector3> {
+    if let Some(t) = trajectory.as_mut() {
+        t.record(None, navigator);
+    }
+
+    for inst in instructions.iter() {
+        navigator
+            .apply(inst.command)
+            .map_err(|e| anyhow!("line {}: {}", inst.line, e))?;
+        if let Some(t) = trajectory.as_mut() {
+            t.record(Some(inst), navigator);
+        }
+    }
+
+    Ok(navigator.position())
+}
+
+fn unsupported(model: &str, command: Command) -> anyhow::Error {
+    anyhow!(
+        "`{}` is not supported by the {} model",
+        command.verb(),
+        model
+    )
+}
+
+// Positions are i64 and every update is checked, so a long course reports an
+// overflow instead of silently wrapping around.
+fn shift(value: &mut i64, by: i64) -> Result<()> {
+    *value = value
+        .checked_add(by)
+        .ok_or_else(|| anyhow!("arithmetic overflow"))?;
+    Ok(())
+}
+
+fn drift(rate: i64, distance: i64) -> Result<i64> {
+    rate.checked_mul(distance)
+        .ok_or_else(|| anyhow!("arithmetic overflow"))
+}
+
+// Part 1: up and down change the depth directly.
+#[derive(Debug, Default)]
+pub struct DirectNavigator {
+    pub x: i64,
+    pub depth: i64,
+}
+
+impl Navigator for DirectNavigator {
+    fn position(&self) -> Vector3 {
+        Vector3 {
+            x: self.x,
+            y: self.depth,
+            z: 0,
+        }
+    }
+
+    fn state(&self) -> Vec<(&'static str, i64)> {
+        vec![("x", self.x), ("depth", self.depth)]
+    }
+
+    fn apply(&mut self, command: Command) -> Result<()> {
+        let amount = command.amount() as i64;
+        match command {
+            Command::Forward(_) => shift(&mut self.x, amount),
+            Command::Back(_) => shift(&mut self.x, -amount),
+            Command::Up(_) => shift(&mut self.depth, -amount),
+            Command::Down(_) => shift(&mut self.depth, amount),
+            _ => Err(unsupported("part1", command)),
+        }
+    }
+}
+
+// Part 2: up and down change the aim, and moving forward dives along it.
+#[derive(Debug, Default)]
+pub struct AimNavigator {
+    pub x: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+impl Navigator for AimNavigator {
+    fn position(&self) -> Vector3 {
+        Vector3 {
+            x: self.x,
+            y: self.depth,
+            z: 0,
+        }
+    }
+
+    fn aim(&self) -> Option<i64> {
+        Some(self.aim)
+    }
+
+    fn state(&self) -> Vec<(&'static str, i64)> {
+        vec![("x", self.x), ("depth", self.depth), ("aim", self.aim)]
+    }
+
+    fn apply(&mut self, command: Command) -> Result<()> {
+        let amount = command.amount() as i64;
+        match command {
+            // back retraces a forward move along the current aim
+            Command::Forward(_) | Command::Back(_) => {
+                let distance = match command {
+                    Command::Back(_) => -amount,
+                    _ => amount,
+                };
+                shift(&mut self.x, distance)?;
+                shift(&mut self.depth, drift(self.aim, distance)?)
+            }
+            Command::Up(_) => shift(&mut self.aim, -amount),
+            Command::Down(_) => shift(&mut self.aim, amount),
+            _ => Err(unsupported("part2", command)),
+        }
+    }
+}
+
+/*
+    The aim model with a lateral axis: port and starboard turn the heading the
+    same way up and down change the aim, and moving forward drifts sideways
+    along it. Negative lateral positions are to port.
+*/
+#[derive(Debug, Default)]
+pub struct Navigator3d {
+    pub position: Vector3,
+    pub aim: i64,
+    pub heading: i64,
+}
+
+impl Navigator for Navigator3d {
+    fn position(&self) -> Vector3 {
+        self.position
+    }
+
+    fn aim(&self) -> Option<i64> {
+        Some(self.aim)
+    }
+
+    fn state(&self) -> Vec<(&'static str, i64)> {
+        vec![
+            ("x", self.position.x),
+            ("depth", self.position.y),
+            ("lateral", self.position.z),
+            ("aim", self.aim),
+            ("heading", self.heading),
+        ]
+    }
+
+    fn apply(&mut self, command: Command) -> Result<()> {
+        let amount = command.amount() as i64;
+        match command {
+            Command::Forward(_) | Command::Back(_) => {
+                let distance = match command {
+                    Command::Back(_) => -amount,
+                    _ => amount,
+                };
+                let position = &mut self.position;
+                shift(&mut position.x, distance)?;
+                shift(&mut position.y, drift(self.aim, distance)?)?;
+                shift(&mut position.z, drift(self.heading, distance)?)
+            }
+            Command::Up(_) => shift(&mut self.aim, -amount),
+            Command::Down(_) => shift(&mut self.aim, amount),
+            Command::Port(_) => shift(&mut self.heading, -amount),
+            Command::Starboard(_) => shift(&mut self.heading, amount),
+        }
+    }
+}
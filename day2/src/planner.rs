@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::command::{Command, Instruction};
+
+// Searches that would try more moves than this are given up on.
+const MAX_SEARCH: usize = 1 << 24;
+
+/*
+    Plans a shortest course under the aim model (`AimNavigator`) that ends at
+    the given horizontal position and depth, as instructions that can be handed
+    straight back to `navigate`. With `max_step` every amount is kept at or
+    below the limit, and the course has to be searched for unless the one
+    without a limit already fits. Targets too far to search are an error.
+*/
+pub fn plan(horizontal: i32, depth: i32, max_step: Option<i32>) -> Result<Vec<Instruction>> {
+    let limit = match max_step {
+        Some(limit) if limit < 1 => bail!("the step limit must be at least 1, got {}", limit),
+        Some(limit) => limit as i64,
+        None => i32::MAX as i64,
+    };
+
+    // Running the plan for (h, d) with forward and back swapped ends at
+    // (-h, -d), and swapping up and down ends at (h, -d), so we only have to
+    // plan for a non-negative horizontal position and depth.
+    let (h, d) = (horizontal as i64, depth as i64);
+    let astern = h < 0;
+    let (h, d) = if astern { (-h, -d) } else { (h, d) };
+    let rising = d < 0;
+    let d = d.abs();
+
+    let moves = closed_form(h, d);
+    let commands = match moves.iter().all(|&(_, amount)| amount <= limit) {
+        // amounts beyond i32::MAX, which only targets at i32::MIN need, are split
+        true => moves
+            .into_iter()
+            .flat_map(|(command, amount)| split(command, amount, i32::MAX as i64))
+            .collect(),
+        false => search(h, d, limit)?,
+    };
+
+    Ok(commands
+        .into_iter()
+        .map(|c| match (c, astern, rising) {
+            (Command::Forward(n), true, _) => Command::Back(n),
+            (Command::Back(n), true, _) => Command::Forward(n),
+            (Command::Down(n), _, true) => Command::Up(n),
+            (Command::Up(n), _, true) => Command::Down(n),
+            (c, _, _) => c,
+        })
+        .enumerate()
+        .map(|(idx, command)| Instruction {
+            command,
+            line: idx + 1,
+        })
+        .collect())
+}
+
+// the number of commands needed to move `amount` in steps of at most `limit`
+fn steps(amount: i64, limit: i64) -> i64 {
+    (amount + limit - 1) / limit
+}
+
+// a command and how far it has to go in total, before splitting into steps
+type Move = (fn(i32) -> Command, i64);
+
+fn split(command: fn(i32) -> Command, amount: i64, limit: i64) -> Vec<Command> {
+    let mut commands = vec![];
+    let mut left = amount;
+    while left > 0 {
+        let step = left.min(limit);
+        commands.push(command(step as i32));
+        left -= step;
+    }
+    commands
+}
+
+/*
+    With no limit the course is `forward h` when `d` is zero, `down d/h,
+    forward h` when `h` divides `d`, and otherwise `forward h-1, down d,
+    forward 1`. No course can beat that: the depth only changes on a forward
+    move made after an aim change. Reaching a depth without moving takes
+    `forward 1, up d, back 1`, as both directions are needed with an aim
+    change in between.
+*/
+fn closed_form(h: i64, d: i64) -> Vec<Move> {
+    match (h, d) {
+        (_, 0) => vec![(Command::Forward, h)],
+        (0, _) => vec![(Command::Forward, 1), (Command::Up, d), (Command::Back, 1)],
+        _ if d % h == 0 => vec![(Command::Down, d / h), (Command::Forward, h)],
+        _ => vec![
+            (Command::Forward, h - 1),
+            (Command::Down, d),
+            (Command::Forward, 1),
+        ],
+    }
+}
+
+/*
+    A breadth-first search over what is left to do: the horizontal distance
+    `y` still to cover and the depth `r` still to gain. Moves take `y` a step
+    nearer or further, and changing the aim by `c` adds `c * y` to the final
+    depth, as every move after it runs at the new aim, so the course is done
+    at (0, 0). States that can't finish within `bound` commands by `fewest`
+    are skipped, and the bound goes up one at a time from the least any
+    course could take, so the first course found is a shortest one.
+*/
+fn search(h: i64, d: i64, limit: i64) -> Result<Vec<Command>> {
+    let mut tried = 0;
+    let mut bound = fewest(h, d, limit);
+    loop {
+        if let Some(commands) = search_within(h, d, limit, bound, &mut tried)? {
+            return Ok(commands);
+        }
+        bound += 1;
+    }
+}
+
+type State = (i64, i64);
+
+fn search_within(
+    h: i64,
+    d: i64,
+    limit: i64,
+    bound: i64,
+    tried: &mut usize,
+) -> Result<Option<Vec<Command>>> {
+    let start = (h, d);
+    // how each state was first reached
+    let mut came_from: HashMap<State, (State, Command)> = HashMap::new();
+    let mut frontier = vec![start];
+    for taken in 1..=bound {
+        let mut next = vec![];
+        for &(y, r) in frontier.iter() {
+            for step in 1..=limit {
+                let amount = step as i32;
+                let moves = [
+                    ((y - step, r), Command::Forward(amount)),
+                    ((y + step, r), Command::Back(amount)),
+                    ((y, r - step * y), Command::Down(amount)),
+                    ((y, r + step * y), Command::Up(amount)),
+                ];
+                // changing the aim at the end of the course does nothing
+                let useful = if y == 0 { 2 } else { 4 };
+                for &(to, command) in moves[..useful].iter() {
+                    *tried += 1;
+                    if *tried > MAX_SEARCH {
+                        bail!(
+                            "{},{} is too far to plan in steps of at most {}",
+                            h,
+                            d,
+                            limit
+                        );
+                    }
+                    if to == start
+                        || came_from.contains_key(&to)
+                        || taken + fewest(to.0, to.1, limit) > bound
+                    {
+                        continue;
+                    }
+                    came_from.insert(to, ((y, r), command));
+                    if to == (0, 0) {
+                        return Ok(Some(retrace(&came_from, start)));
+                    }
+                    next.push(to);
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok(None)
+}
+
+fn retrace(came_from: &HashMap<State, (State, Command)>, start: State) -> Vec<Command> {
+    let mut commands = vec![];
+    let mut at = (0, 0);
+    while at != start {
+        let (from, command) = came_from[&at];
+        commands.push(command);
+        at = from;
+    }
+    commands.reverse();
+    commands
+}
+
+/*
+    The fewest commands that could finish from `y` to go with `r` depth to
+    gain. Getting home takes at least `|y| / limit` steps, and any depth left
+    needs the sub to go out to some `p` at least as far as it is, taking
+    `(2p - |y|) / limit` steps, and then at least `r / (limit * p)` aim
+    changes. Over real `p`, that is cheapest at `p = sqrt(r / 2)` or as near
+    as it can get.
+*/
+fn fewest(y: i64, r: i64, limit: i64) -> i64 {
+    let home = steps(y.abs(), limit);
+    if r == 0 {
+        return home;
+    }
+    let (y, r, limit) = (y.abs() as f64, r.abs() as f64, limit as f64);
+    let p = (r / 2.0).sqrt().max(y).max(1.0);
+    let relaxed = (2.0 * p - y + r / p) / limit;
+    // shaved so rounding can't push it past a course that exists
+    (home + 1).max((relaxed * (1.0 - 1e-9)).ceil() as i64)
+}
+
+pub fn to_script(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|i| format!("{} {}\n", i.command.verb(), i.command.amount()))
+        .collect()
+}
+
+pub fn parse_target(s: &str) -> Result<(i32, i32)> {
+    let (h, d) = s
+        .split_once(',')
+        .ok_or_else(|| anyhow!("expected a target of the form `<horizontal>,<depth>`"))?;
+    Ok((h.trim().parse()?, d.trim().parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the fewest commands to each (x, depth), from a search over every state of the sub in a box
+    fn brute_force(limit: i64) -> HashMap<(i64, i64), usize> {
+        let inside = |(x, depth, aim): (i64, i64, i64)| {
+            x.abs() <= 16 && depth.abs() <= 64 && aim.abs() <= 16
+        };
+        let mut fewest = HashMap::from([((0, 0), 0)]);
+        let mut seen = std::collections::HashSet::from([(0, 0, 0)]);
+        let mut frontier = vec![(0, 0, 0)];
+        for taken in 1.. {
+            let mut next = vec![];
+            for &(x, depth, aim) in frontier.iter() {
+                for step in 1..=limit {
+                    for to in [
+                        (x + step, depth + aim * step, aim),
+                        (x - step, depth - aim * step, aim),
+                        (x, depth, aim + step),
+                        (x, depth, aim - step),
+                    ] {
+                        if inside(to) && seen.insert(to) {
+                            fewest.entry((to.0, to.1)).or_insert(taken);
+                            next.push(to);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                return fewest;
+            }
+            frontier = next;
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn limited_courses_match_brute_force() {
+        for limit in 1..=4 {
+            let fewest = brute_force(limit);
+            for h in -8..=8 {
+                for d in -24..=24 {
+                    let course = plan(h, d, Some(limit as i32)).unwrap();
+                    assert_eq!(
+                        fewest[&(h as i64, d as i64)],
+                        course.len(),
+                        "{},{} in steps of {}",
+                        h,
+                        d,
+                        limit
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_targets() {
+        let len = |h, d, limit| plan(h, d, limit).map(|c| c.len());
+        assert_eq!(3, len(2_000_000_000, 1_999_999_999, None).unwrap());
+        // too many courses to look through, but no hang finding that out
+        assert!(len(2_000_000_000, 1_999_999_999, Some(1_000_000)).is_err());
+    }
+}
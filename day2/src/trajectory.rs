@@ -0,0 +1,114 @@
+use std::fmt::{self, Write};
+
+use crate::{command::Instruction, navigator::Navigator, Vector3};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Waypoint {
+    // the source line of the instruction that led here, `None` for the start
+    pub line: Option<usize>,
+    pub position: Vector3,
+    pub aim: Option<i64>,
+    // the model's whole state, as `Navigator::state` gives it
+    pub state: Vec<(&'static str, i64)>,
+}
+
+impl fmt::Display for Waypoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (name, value)) in self.state.iter().enumerate() {
+            let sep = if idx == 0 { "" } else { ", " };
+            write!(f, "{}{}={}", sep, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+// Every state the sub passes through, starting with where it began, so
+// `waypoints[k]` is the state after `k` instructions have run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trajectory {
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Trajectory {
+    pub fn new() -> Trajectory {
+        Trajectory::default()
+    }
+
+    pub fn record(&mut self, instruction: Option<&Instruction>, navigator: &dyn Navigator) {
+        self.waypoints.push(Waypoint {
+            line: instruction.map(|i| i.line),
+            position: navigator.position(),
+            aim: navigator.aim(),
+            state: navigator.state(),
+        });
+    }
+
+    pub fn after(&self, instructions: usize) -> Option<&Waypoint> {
+        self.waypoints.get(instructions)
+    }
+
+    // one column for each part of the model's state
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,line");
+        if let Some(first) = self.waypoints.first() {
+            for (name, _) in first.state.iter() {
+                write!(csv, ",{}", name).unwrap();
+            }
+        }
+        csv.push('\n');
+        for (step, wp) in self.waypoints.iter().enumerate() {
+            let line = wp.line.map(|l| l.to_string()).unwrap_or_default();
+            write!(csv, "{},{}", step, line).unwrap();
+            for (_, value) in wp.state.iter() {
+                write!(csv, ",{}", value).unwrap();
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // horizontal position along the x axis, depth increasing downwards
+    pub fn to_svg(&self) -> String {
+        let xs = self.waypoints.iter().map(|wp| wp.position.x);
+        let ys = self.waypoints.iter().map(|wp| wp.position.y);
+        let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+        // always keep the surface in view
+        let (min_y, max_y) = (ys.clone().min().unwrap_or(0).min(0), ys.max().unwrap_or(0));
+        let width = (max_x - min_x).max(1);
+        let height = (max_y - min_y).max(1);
+        let pad = width.max(height) / 20 + 1;
+
+        let points = self
+            .waypoints
+            .iter()
+            .map(|wp| format!("{},{}", wp.position.x, wp.position.y))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" preserveAspectRatio="none">"#,
+            min_x - pad,
+            min_y - pad,
+            width + pad * 2,
+            height + pad * 2
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"  <line x1="{}" y1="0" x2="{}" y2="0" stroke="steelblue" stroke-dasharray="4" vector-effect="non-scaling-stroke"/>"#,
+            min_x - pad,
+            max_x + pad
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"  <polyline points="{}" fill="none" stroke="black" vector-effect="non-scaling-stroke"/>"#,
+            points
+        )
+        .unwrap();
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
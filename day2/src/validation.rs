@@ -0,0 +1,89 @@
+use std::fmt;
+
+use crate::trajectory::{Trajectory, Waypoint};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Constraints {
+    pub no_surfacing: bool,
+    pub max_depth: Option<i64>,
+    // the steepest the sub may pitch, up or down
+    pub max_aim: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breach {
+    Surfaced,
+    TooDeep(i64),
+    AimTooSteep(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    // index into the course of the instruction that caused it, starting from 0
+    pub index: usize,
+    pub line: usize,
+    pub breach: Breach,
+    pub waypoint: Waypoint,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+
+    // models without an aim can never pitch too steeply
+    fn breaches(&self, waypoint: &Waypoint) -> Vec<Breach> {
+        let mut result = vec![];
+        let depth = waypoint.position.y;
+        if self.no_surfacing && depth < 0 {
+            result.push(Breach::Surfaced);
+        }
+        match self.max_depth {
+            Some(max) if depth > max => result.push(Breach::TooDeep(max)),
+            _ => {}
+        }
+        match (self.max_aim, waypoint.aim) {
+            (Some(max), Some(aim)) if aim.unsigned_abs() > max.unsigned_abs() => {
+                result.push(Breach::AimTooSteep(max))
+            }
+            _ => {}
+        }
+        result
+    }
+}
+
+// Checks the state after every instruction of a recorded course.
+pub fn validate(trajectory: &Trajectory, constraints: &Constraints) -> Vec<Violation> {
+    trajectory
+        .waypoints
+        .iter()
+        .enumerate()
+        .skip(1)
+        .flat_map(|(step, wp)| {
+            constraints
+                .breaches(wp)
+                .into_iter()
+                .map(move |breach| Violation {
+                    index: step - 1,
+                    line: wp.line.unwrap_or_default(),
+                    breach,
+                    waypoint: wp.clone(),
+                })
+        })
+        .collect()
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.breach {
+            Breach::Surfaced => String::from("above the surface"),
+            Breach::TooDeep(max) => format!("deeper than {}", max),
+            Breach::AimTooSteep(max) => format!("aim steeper than {}", max),
+        };
+        write!(
+            f,
+            "instruction {} (line {}): {} at {}",
+            self.index, self.line, what, self.waypoint
+        )
+    }
+}
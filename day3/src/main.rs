@@ -1,64 +1,484 @@
-use bitvec::prelude::*;
+mod rating;
+
+use std::{fmt, str::FromStr, time::Instant};
+
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigUint;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rating::rating;
+
+// the first character of a line is the most significant digit, and is stored at index 0
+type Digits = Vec<u8>;
+
+/*
+    Usage: day3 [--base <k>] [--columns] [--gamma-tie <t>] [--oxygen-tie <t>] [--co2-tie <t>]
+           day3 [--base <k>] --bench [lines [width]]
+
+    The report is read as base `k` digits, 2 unless given. A tie policy `<t>`
+    is `high`, `low` or `error`, and decides which digit wins when the counts
+    are level; in binary `one` and `zero` mean the same as `high` and `low`.
+    `--columns` prints the counts behind gamma and epsilon for every column.
+
+    The bench mode generates a random report, a million lines of 32 digits
+    unless given, and times the original filtering rating search against the
+    partitioning one.
+*/
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1).peekable();
+    let mut ties = TieBreaks::default();
+    let mut base = 2;
+    let mut show_columns = false;
+    let mut bench = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--bench" => {
+                let mut optional = || args.next_if(|a| !a.starts_with("--"));
+                let lines = optional()
+                    .map(|a| a.parse())
+                    .transpose()?
+                    .unwrap_or(1_000_000);
+                let width = optional().map(|a| a.parse()).transpose()?.unwrap_or(32);
+                bench = Some((lines, width));
+            }
+            "--base" => base = value()?.parse()?,
+            "--columns" => show_columns = true,
+            "--gamma-tie" => ties.gamma = value()?.parse()?,
+            "--oxygen-tie" => ties.oxygen = value()?.parse()?,
+            "--co2-tie" => ties.co2 = value()?.parse()?,
+            _ => bail!("unknown argument `{}`", arg),
+        }
+    }
+    if !(2..=36).contains(&base) {
+        bail!("the base must be between 2 and 36, got {}", base);
+    }
+
+    if let Some((lines, width)) = bench {
+        return benchmark(lines, width, base, &ties);
+    }
 
-fn main() {
     let input = include_str!("input");
-    let inputs = input
-        .lines()
-        .map(|l| l.chars().map(|c| c == '1').collect::<BitVec>())
-        .collect::<Vec<_>>();
+    let inputs = parse_report(input, base)?;
 
-    let (gamma, epsilon) = gamma_and_epsilon(&inputs);
+    let columns = column_report(&inputs, base, &ties);
+    if show_columns {
+        println!("pos  counts by digit  gamma  epsilon");
+        for (idx, c) in columns.iter().enumerate() {
+            let show = |digit: Option<u8>| match digit {
+                Some(d) => std::char::from_digit(d as u32, base).unwrap().to_string(),
+                None => String::from("-"),
+            };
+            let counts = c
+                .counts
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "{:>3}  {:<15} {:>6} {:>8}",
+                idx,
+                counts,
+                show(c.gamma),
+                show(c.epsilon)
+            );
+        }
+    }
+
+    let (gamma, epsilon) = gamma_and_epsilon(&columns)?;
     println!(
-        "Part 1: {:#?}",
-        gamma.load::<usize>() * epsilon.load::<usize>()
+        "Part 1: {}",
+        to_number(&gamma, base) * to_number(&epsilon, base)
     );
 
-    let oxygen = filter_away(&inputs, true).load::<usize>();
-    let co2 = filter_away(&inputs, false).load::<usize>();
-    println!("Part 2: {:#?}", oxygen * co2)
+    let oxygen = to_number(&rating(&inputs, base, Rating::Oxygen, &ties)?, base);
+    let co2 = to_number(&rating(&inputs, base, Rating::Co2, &ties)?, base);
+    println!("Part 2: {}", oxygen * co2);
+    Ok(())
 }
 
-fn filter_away(inputs: &[BitVec], co2: bool) -> BitVec {
-    let mut filtered = inputs.to_owned();
-    let mut idx = 0;
+fn benchmark(lines: usize, width: usize, base: u32, ties: &TieBreaks) -> Result<()> {
+    if lines == 0 || width == 0 {
+        bail!("the bench needs at least one line of at least one digit");
+    }
+    let mut rng = StdRng::seed_from_u64(3);
+    let report = (0..lines)
+        .map(|_| {
+            (0..width)
+                .map(|_| rng.gen_range(0..base) as u8)
+                .collect::<Digits>()
+        })
+        .collect::<Vec<_>>();
+    println!(
+        "Generated {} lines of {} base {} digits",
+        lines, width, base
+    );
+
+    let start = Instant::now();
+    let filtered = (
+        filter_away(&report, base, Rating::Oxygen, ties)?,
+        filter_away(&report, base, Rating::Co2, ties)?,
+    );
+    println!("filter_away: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let partitioned = (
+        rating(&report, base, Rating::Oxygen, ties)?,
+        rating(&report, base, Rating::Co2, ties)?,
+    );
+    println!("rating: {:?}", start.elapsed());
+
+    if filtered != partitioned {
+        bail!(
+            "the two rating searches disagree: {:?} against {:?}",
+            filtered,
+            partitioned
+        );
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    PreferHigh,
+    PreferLow,
+    Error,
+}
+
+impl FromStr for TieBreak {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "high" | "one" => Ok(TieBreak::PreferHigh),
+            "low" | "zero" => Ok(TieBreak::PreferLow),
+            "error" => Ok(TieBreak::Error),
+            _ => bail!("unknown tie policy `{}`, expected high, low or error", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rating {
+    Gamma,
+    Epsilon,
+    Oxygen,
+    Co2,
+}
+
+impl Rating {
+    fn least_common(&self) -> bool {
+        matches!(self, Rating::Epsilon | Rating::Co2)
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rating::Gamma => write!(f, "gamma"),
+            Rating::Epsilon => write!(f, "epsilon"),
+            Rating::Oxygen => write!(f, "oxygen"),
+            Rating::Co2 => write!(f, "CO2"),
+        }
+    }
+}
+
+// The digit each rating keeps when the counts are level. The defaults are
+// the puzzle's rules. Epsilon breaks ties the opposite way to gamma, so in
+// binary it stays the complement of gamma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TieBreaks {
+    pub gamma: TieBreak,
+    pub oxygen: TieBreak,
+    pub co2: TieBreak,
+}
+
+impl Default for TieBreaks {
+    fn default() -> Self {
+        TieBreaks {
+            gamma: TieBreak::PreferHigh,
+            oxygen: TieBreak::PreferHigh,
+            co2: TieBreak::PreferLow,
+        }
+    }
+}
 
-    while !filtered.is_empty() {
-        let mut most_common = most_common_val_for_bit(&filtered, idx);
-        if co2 {
-            most_common = !most_common
+impl TieBreaks {
+    fn policy(&self, rating: Rating) -> TieBreak {
+        match rating {
+            Rating::Gamma => self.gamma,
+            Rating::Epsilon => match self.gamma {
+                TieBreak::PreferHigh => TieBreak::PreferLow,
+                TieBreak::PreferLow => TieBreak::PreferHigh,
+                TieBreak::Error => TieBreak::Error,
+            },
+            Rating::Oxygen => self.oxygen,
+            Rating::Co2 => self.co2,
         }
+    }
 
-        filtered = filtered
-            .iter()
-            .filter(|i| i[idx] == most_common)
-            .cloned()
+    /*
+        The digit a rating keeps at position `idx`, given how many lines have
+        each digit there. Gamma and epsilon pick from every digit of the base,
+        so a digit nobody uses can be the least common, while the oxygen and
+        CO2 ratings only pick from the digits still present in their lines.
+    */
+    pub fn choose(&self, rating: Rating, idx: usize, counts: &[usize]) -> Result<u8> {
+        let candidates = (0..counts.len()).filter(|&d| match rating {
+            Rating::Oxygen | Rating::Co2 => counts[d] > 0,
+            _ => true,
+        });
+        let best = match rating.least_common() {
+            true => candidates.clone().map(|d| counts[d]).min(),
+            false => candidates.clone().map(|d| counts[d]).max(),
+        }
+        .ok_or_else(|| anyhow!("{} rating has no lines left", rating))?;
+        let tied = candidates
+            .filter(|&d| counts[d] == best)
             .collect::<Vec<_>>();
 
-        // cant figure out bitvecs and msb vs lsb, nothing works, so just reverse the bits
-        if filtered.len() == 1 {
-            filtered[0].reverse();
-            return filtered[0].clone();
+        match (tied.len(), self.policy(rating)) {
+            (1, _) | (_, TieBreak::PreferLow) => Ok(tied[0] as u8),
+            (_, TieBreak::PreferHigh) => Ok(tied[tied.len() - 1] as u8),
+            (_, TieBreak::Error) => bail!(
+                "{} rating is tied at position {} with {} lines each",
+                rating,
+                idx,
+                best
+            ),
         }
-        idx += 1;
     }
-    panic!() // Shouldn't reach here!
 }
 
-fn gamma_and_epsilon(inputs: &[BitVec]) -> (BitVec, BitVec) {
-    let mut gamma = bitvec![];
-    let mut epsilon = bitvec![];
+fn parse_report(input: &str, base: u32) -> Result<Vec<Digits>> {
+    let mut report: Vec<Digits> = vec![];
+    for (idx, line) in input.lines().map(|l| l.trim()).enumerate() {
+        let digits = line
+            .chars()
+            .map(|c| match c.to_digit(base) {
+                Some(d) => Ok(d as u8),
+                None => bail!("line {}: `{}` is not a base {} digit", idx + 1, c, base),
+            })
+            .collect::<Result<Digits>>()?;
+
+        if let Some(first) = report.first() {
+            if first.len() != digits.len() {
+                bail!(
+                    "line {}: expected {} digits like the first line, found {}",
+                    idx + 1,
+                    first.len(),
+                    digits.len()
+                );
+            }
+        }
+        report.push(digits);
+    }
+
+    match report.first() {
+        Some(first) if !first.is_empty() => Ok(report),
+        _ => bail!("the report is empty"),
+    }
+}
+
+fn to_number(digits: &[u8], base: u32) -> BigUint {
+    BigUint::from_radix_be(digits, base).unwrap()
+}
+
+fn filter_away(inputs: &[Digits], base: u32, rating: Rating, ties: &TieBreaks) -> Result<Digits> {
+    let mut filtered = inputs.to_owned();
+
     for idx in 0..inputs[0].len() {
-        // count the 1s, count the zeros
-        let most_common = most_common_val_for_bit(inputs, idx);
-        gamma.push(most_common);
-        epsilon.push(!most_common);
+        if filtered.len() == 1 {
+            break;
+        }
+
+        // when every line left agrees on this digit it's the only choice, and nothing is filtered
+        let keep = ties.choose(rating, idx, &count_digits(&filtered, idx, base))?;
+        filtered.retain(|i| i[idx] == keep);
+    }
+
+    // anything left over after the last digit is a duplicate of the same value
+    Ok(filtered.swap_remove(0))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    counts: Vec<usize>,
+    // the digits gamma and epsilon take here, `None` when it's a tie the policy refuses to break
+    gamma: Option<u8>,
+    epsilon: Option<u8>,
+}
+
+fn column_report(inputs: &[Digits], base: u32, ties: &TieBreaks) -> Vec<Column> {
+    (0..inputs[0].len())
+        .map(|idx| {
+            let counts = count_digits(inputs, idx, base);
+            Column {
+                gamma: ties.choose(Rating::Gamma, idx, &counts).ok(),
+                epsilon: ties.choose(Rating::Epsilon, idx, &counts).ok(),
+                counts,
+            }
+        })
+        .collect()
+}
+
+fn gamma_and_epsilon(columns: &[Column]) -> Result<(Digits, Digits)> {
+    let mut gamma = Digits::new();
+    let mut epsilon = Digits::new();
+    for (idx, column) in columns.iter().enumerate() {
+        let tied = |rating| {
+            let count = column.counts.iter().max().unwrap();
+            anyhow!(
+                "{} rating is tied at position {} with {} lines each",
+                rating,
+                idx,
+                count
+            )
+        };
+        gamma.push(column.gamma.ok_or_else(|| tied(Rating::Gamma))?);
+        epsilon.push(column.epsilon.ok_or_else(|| tied(Rating::Epsilon))?);
     }
-    // cant figure out bitvecs and msb vs lsb, nothing works, so just reverse the bits
-    gamma.reverse();
-    epsilon.reverse();
-    (gamma, epsilon)
+    Ok((gamma, epsilon))
 }
 
-fn most_common_val_for_bit(inputs: &[BitVec], idx: usize) -> bool {
-    inputs.iter().map(|i| i[idx]).filter(|&i| i).count() * 2 >= inputs.len()
+fn count_digits<'a, I>(lines: I, idx: usize, base: u32) -> Vec<usize>
+where
+    I: IntoIterator<Item = &'a Digits>,
+{
+    let mut counts = vec![0; base as usize];
+    for line in lines {
+        counts[line[idx] as usize] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
+
+    fn gamma_and_epsilon_of(
+        inputs: &[Digits],
+        base: u32,
+        ties: &TieBreaks,
+    ) -> Result<(Digits, Digits)> {
+        gamma_and_epsilon(&column_report(inputs, base, ties))
+    }
+
+    #[test]
+    fn example() {
+        let inputs = parse_report(EXAMPLE, 2).unwrap();
+        let ties = TieBreaks::default();
+        let (gamma, epsilon) = gamma_and_epsilon_of(&inputs, 2, &ties).unwrap();
+        assert_eq!(BigUint::from(22u32), to_number(&gamma, 2));
+        assert_eq!(BigUint::from(9u32), to_number(&epsilon, 2));
+        for search in [filter_away, rating] {
+            let oxygen = search(&inputs, 2, Rating::Oxygen, &ties).unwrap();
+            assert_eq!(BigUint::from(23u32), to_number(&oxygen, 2));
+            let co2 = search(&inputs, 2, Rating::Co2, &ties).unwrap();
+            assert_eq!(BigUint::from(10u32), to_number(&co2, 2));
+        }
+    }
+
+    #[test]
+    fn rating_matches_filtering() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let ties = TieBreaks::default();
+        for base in [2, 3, 16] {
+            for width in 1..=10 {
+                let report = (0..200)
+                    .map(|_| (0..width).map(|_| rng.gen_range(0..base) as u8).collect())
+                    .collect::<Vec<Digits>>();
+                for r in [Rating::Oxygen, Rating::Co2] {
+                    assert_eq!(
+                        filter_away(&report, base, r, &ties).unwrap(),
+                        rating(&report, base, r, &ties).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tie_policies() {
+        // the first column is tied, and so is the second among the lines left
+        let inputs = parse_report("10\n11\n01\n00", 2).unwrap();
+        let mut ties = TieBreaks {
+            gamma: TieBreak::PreferLow,
+            oxygen: TieBreak::PreferLow,
+            co2: TieBreak::PreferHigh,
+        };
+
+        let columns = column_report(&inputs, 2, &ties);
+        assert_eq!(vec![2, 2], columns[0].counts);
+        assert_eq!((Some(0), Some(1)), (columns[0].gamma, columns[0].epsilon));
+        let (gamma, epsilon) = gamma_and_epsilon(&columns).unwrap();
+        assert_eq!((vec![0, 0], vec![1, 1]), (gamma, epsilon));
+        for search in [filter_away, rating] {
+            assert_eq!(
+                vec![0, 0],
+                search(&inputs, 2, Rating::Oxygen, &ties).unwrap()
+            );
+            assert_eq!(vec![1, 1], search(&inputs, 2, Rating::Co2, &ties).unwrap());
+        }
+
+        ties.gamma = TieBreak::Error;
+        ties.co2 = TieBreak::Error;
+        assert_eq!(None, column_report(&inputs, 2, &ties)[0].gamma);
+        let err = gamma_and_epsilon_of(&inputs, 2, &ties).unwrap_err();
+        assert_eq!(
+            "gamma rating is tied at position 0 with 2 lines each",
+            err.to_string()
+        );
+        let err = rating(&inputs, 2, Rating::Co2, &ties).unwrap_err();
+        assert_eq!(
+            "CO2 rating is tied at position 0 with 2 lines each",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn ternary_and_hex() {
+        let inputs = parse_report("120\n102\n221\n100\n012", 3).unwrap();
+        let ties = TieBreaks::default();
+        let (gamma, epsilon) = gamma_and_epsilon_of(&inputs, 3, &ties).unwrap();
+        assert_eq!((vec![1, 2, 2], vec![0, 1, 1]), (gamma, epsilon));
+        // oxygen keeps 1xx then 10x, and the tie between 100 and 102 goes high
+        assert_eq!(
+            vec![1, 0, 2],
+            rating(&inputs, 3, Rating::Oxygen, &ties).unwrap()
+        );
+        // CO2 ties between 0xx and 2xx, one line each, and goes low
+        assert_eq!(
+            vec![0, 1, 2],
+            rating(&inputs, 3, Rating::Co2, &ties).unwrap()
+        );
+
+        let inputs = parse_report("ff\nf0\n0a", 16).unwrap();
+        let (gamma, _) = gamma_and_epsilon_of(&inputs, 16, &ties).unwrap();
+        assert_eq!(BigUint::from(0xffu32), to_number(&gamma, 16));
+        assert!(parse_report("12\n1g", 16).is_err());
+    }
+
+    #[test]
+    fn wider_than_64_bits() {
+        let line = format!("1{}1", "0".repeat(98));
+        let inputs = parse_report(&format!("{}\n{}", line, line), 2).unwrap();
+        let (gamma, _) = gamma_and_epsilon_of(&inputs, 2, &TieBreaks::default()).unwrap();
+        let expected = (BigUint::from(1u32) << 99u32) + 1u32;
+        assert_eq!(expected, to_number(&gamma, 2));
+    }
+
+    #[test]
+    fn inconsistent_widths_are_rejected() {
+        let err = parse_report("0101\n011\n1100", 2).unwrap_err();
+        assert_eq!(
+            "line 2: expected 4 digits like the first line, found 3",
+            err.to_string()
+        );
+    }
 }
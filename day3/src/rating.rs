@@ -0,0 +1,42 @@
+use anyhow::Result;
+
+use crate::{count_digits, Digits, Rating, TieBreaks};
+
+/*
+    Finds a rating by narrowing down a range of the report one digit at a
+    time, like a lazy most-significant-digit-first radix sort: the lines still
+    in the running are partitioned in place so those with the chosen digit come
+    first, which leaves the range for it contiguous. Nothing is cloned until the
+    answer is known, and the work is at most O(n·w), usually closer to O(n) as
+    the range shrinks each step.
+*/
+pub fn rating(report: &[Digits], base: u32, rating: Rating, ties: &TieBreaks) -> Result<Digits> {
+    let width = report.first().map(|d| d.len()).unwrap_or(0);
+    let mut lines = report.iter().collect::<Vec<_>>();
+    let mut range = &mut lines[..];
+
+    for idx in 0..width {
+        if range.len() <= 1 {
+            break;
+        }
+
+        let counts = count_digits(range.iter().copied(), idx, base);
+        let keep = ties.choose(rating, idx, &counts)?;
+        let kept = partition(range, idx, keep);
+        range = &mut range[..kept];
+    }
+
+    Ok(range[0].clone())
+}
+
+// moves the lines with `digit` at `idx` to the front and returns how many there are
+fn partition(lines: &mut [&Digits], idx: usize, digit: u8) -> usize {
+    let mut kept = 0;
+    for i in 0..lines.len() {
+        if lines[i][idx] == digit {
+            lines.swap(kept, i);
+            kept += 1;
+        }
+    }
+    kept
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::{unmarked_sum, BingoNumber, WinRule, WinningLine};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Win {
+    pub board: usize,
+    // index into the calls of the number that won it
+    pub call_index: usize,
+    pub number: usize,
+    pub line: WinningLine,
+    pub score: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoWin {
+    pub board: usize,
+    pub unmarked_sum: usize,
+}
+
+// Every board in the order it won, then those that never did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameReport {
+    pub wins: Vec<Win>,
+    pub never_won: Vec<NoWin>,
+}
+
+pub fn play(boards: Vec<Array2<BingoNumber>>, calls: &[usize], rules: &[WinRule]) -> GameReport {
+    let mut game = Game::new(boards, rules);
+    let mut wins = vec![];
+    for (call_index, &number) in calls.iter().enumerate() {
+        for (board, line) in game.call(number) {
+            wins.push(Win {
+                board,
+                call_index,
+                number,
+                line,
+                score: unmarked_sum(&game.boards[board]) * number,
+            });
+        }
+    }
+
+    let never_won = (0..game.boards.len())
+        .filter(|&b| !game.won[b])
+        .map(|board| NoWin {
+            board,
+            unmarked_sum: unmarked_sum(&game.boards[board]),
+        })
+        .collect();
+    GameReport { wins, never_won }
+}
+
+// How many marked cells each line of a board has.
+#[derive(Debug, Clone)]
+struct Tally {
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    diagonal: usize,
+    anti_diagonal: usize,
+    corners: usize,
+    marked: usize,
+}
+
+/*
+    Plays many boards at once. Every number is indexed to the cells holding
+    it, and each board keeps a count of the marked cells in each of its lines,
+    so a call only touches the cells with that number instead of rescanning
+    every board. A line is complete when its count reaches its length.
+*/
+pub struct Game {
+    pub boards: Vec<Array2<BingoNumber>>,
+    rules: Vec<WinRule>,
+    cells: HashMap<usize, Vec<(usize, usize, usize)>>,
+    tallies: Vec<Tally>,
+    won: Vec<bool>,
+}
+
+impl Game {
+    pub fn new(boards: Vec<Array2<BingoNumber>>, rules: &[WinRule]) -> Game {
+        let mut cells: HashMap<usize, Vec<(usize, usize, usize)>> = HashMap::new();
+        for (b, board) in boards.iter().enumerate() {
+            for ((r, c), n) in board.indexed_iter() {
+                cells.entry(n.value).or_default().push((b, r, c));
+            }
+        }
+        let tallies = boards
+            .iter()
+            .map(|board| {
+                let (height, width) = board.dim();
+                Tally {
+                    rows: vec![0; height],
+                    columns: vec![0; width],
+                    diagonal: 0,
+                    anti_diagonal: 0,
+                    corners: 0,
+                    marked: 0,
+                }
+            })
+            .collect();
+
+        Game {
+            won: vec![false; boards.len()],
+            boards,
+            rules: rules.to_vec(),
+            cells,
+            tallies,
+        }
+    }
+
+    // marks the number everywhere it appears, and returns the boards that won
+    // because of it in board order, along with the line they won on
+    pub fn call(&mut self, value: usize) -> Vec<(usize, WinningLine)> {
+        let mut winners = vec![];
+        let cells = match self.cells.get(&value) {
+            Some(cells) => cells,
+            None => return winners,
+        };
+
+        for &(b, r, c) in cells {
+            let cell = &mut self.boards[b][[r, c]];
+            if cell.selected {
+                continue;
+            }
+            cell.selected = true;
+
+            let (height, width) = self.boards[b].dim();
+            let tally = &mut self.tallies[b];
+            tally.rows[r] += 1;
+            tally.columns[c] += 1;
+            tally.marked += 1;
+            if height == width && r == c {
+                tally.diagonal += 1;
+            }
+            if height == width && r + c == width - 1 {
+                tally.anti_diagonal += 1;
+            }
+            if (r == 0 || r == height - 1) && (c == 0 || c == width - 1) {
+                tally.corners += 1;
+            }
+
+            if !self.won[b] && !winners.iter().any(|&(w, _)| w == b) {
+                if let Some(line) = self.completed_line(b, r, c) {
+                    winners.push((b, line));
+                }
+            }
+        }
+
+        for &(b, _) in winners.iter() {
+            self.won[b] = true;
+        }
+        winners.sort_by_key(|&(b, _)| b);
+        winners
+    }
+
+    // Only the lines through the newly marked cell can have just been
+    // completed. When one has, the board's winning line is reported the same
+    // way `winning_line` would pick it, by rule order and then lowest index.
+    fn completed_line(&self, b: usize, r: usize, c: usize) -> Option<WinningLine> {
+        let (height, width) = self.boards[b].dim();
+        let tally = &self.tallies[b];
+        let corners = height.min(2) * width.min(2);
+
+        let completed = self.rules.iter().any(|rule| match rule {
+            WinRule::Rows => tally.rows[r] == width,
+            WinRule::Columns => tally.columns[c] == height,
+            WinRule::Diagonals => {
+                height == width && (tally.diagonal == width || tally.anti_diagonal == width)
+            }
+            WinRule::FourCorners => tally.corners == corners,
+            WinRule::FullCard => tally.marked == height * width,
+        });
+        if !completed {
+            return None;
+        }
+
+        self.rules.iter().find_map(|rule| match rule {
+            WinRule::Rows => tally
+                .rows
+                .iter()
+                .position(|&n| n == width)
+                .map(WinningLine::Row),
+            WinRule::Columns => tally
+                .columns
+                .iter()
+                .position(|&n| n == height)
+                .map(WinningLine::Column),
+            WinRule::Diagonals if height == width && tally.diagonal == width => {
+                Some(WinningLine::Diagonal)
+            }
+            WinRule::Diagonals if height == width && tally.anti_diagonal == width => {
+                Some(WinningLine::AntiDiagonal)
+            }
+            WinRule::Diagonals => None,
+            WinRule::FourCorners => (tally.corners == corners).then_some(WinningLine::FourCorners),
+            WinRule::FullCard => (tally.marked == height * width).then_some(WinningLine::FullCard),
+        })
+    }
+}
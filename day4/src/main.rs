@@ -1,35 +1,225 @@
-use ndarray::{Array2, ArrayBase, Axis, Dim, ViewRepr};
+mod game;
+mod montecarlo;
+mod tournament;
+mod validation;
 
-fn main() {
+use std::{str::FromStr, time::Instant};
+
+use anyhow::{anyhow, bail, Result};
+use ndarray::Array2;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+
+/*
+    Usage: day4 [--rules <rule,...>] [--json <path>] [--simulate <trials> [--seed <n>]]
+           day4 [--rules <rule,...>] --bench [boards]
+
+    The rules that make a board win, out of rows, columns, diagonals, corners
+    and full. Rows and columns are used unless told otherwise. `--json` also
+    writes the game report to the given path. `--simulate` replays the game
+    with the calls shuffled and estimates each board's odds of winning first
+    and last.
+
+    The input may start with several lines of calls before the boards. Each
+    sequence is then played against fresh copies of the boards and the results
+    are tabled per sequence and per board; `--json` writes one report per
+    sequence and `--simulate` shuffles the first sequence. Problems found in
+    the input, such as numbers repeated on a board or never called, are
+    reported before playing.
+
+    The bench mode plays the given number of random 5x5 boards, 10,000 unless
+    given, marking them by scanning every board against the indexed `Game`.
+*/
+fn main() -> Result<()> {
     let input = include_str!("input");
-    let lines = input.lines().collect::<Vec<_>>();
 
-    let calls = lines[0]
-        .split(',')
-        .map(|c| c.parse::<usize>().unwrap())
-        .collect::<Vec<_>>();
+    let mut rules = vec![WinRule::Rows, WinRule::Columns];
+    let mut bench = None;
+    let mut json = None;
+    let mut trials = None;
+    let mut seed = 0;
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--rules" => {
+                rules = value()?
+                    .split(',')
+                    .map(|r| r.parse())
+                    .collect::<Result<_>>()?
+            }
+            "--bench" => {
+                let boards = args.next_if(|a| !a.starts_with("--"));
+                bench = Some(boards.map(|a| a.parse()).transpose()?.unwrap_or(10_000));
+            }
+            "--json" => json = Some(value()?),
+            "--simulate" => trials = Some(value()?.parse()?),
+            "--seed" => seed = value()?.parse()?,
+            _ => bail!("unknown argument `{}`", arg),
+        }
+    }
+    if let Some(boards) = bench {
+        benchmark(boards, &rules);
+        return Ok(());
+    }
+
+    let sequences = parse_calls(input)?;
+    let boards = parse_boards(input)?;
+    for problem in validation::validate(&sequences, &boards) {
+        println!("warning: {}", problem);
+    }
 
-    let mut boards = parse_boards(input);
+    let reports = tournament::play(&boards, &sequences, &rules);
+    if let [report] = &reports[..] {
+        print_report(report);
+        if let Some(path) = json {
+            std::fs::write(path, serde_json::to_string_pretty(report)?)?;
+        }
+    } else {
+        print_tournament(boards.len(), &reports);
+        if let Some(path) = json {
+            std::fs::write(path, serde_json::to_string_pretty(&reports)?)?;
+        }
+    }
 
-    for call in calls {
-        select(&mut boards, call);
-        while let Some((idx, winning_board)) = detect_winner(&boards) {
+    if let Some(trials) = trials {
+        println!("Odds over {} shuffled games (95% intervals):", trials);
+        println!("board  wins first              wins last");
+        for odds in montecarlo::estimate(&boards, &sequences[0], &rules, trials, seed) {
+            let show =
+                |e: montecarlo::Estimate| format!("{:.4} [{:.4}, {:.4}]", e.mean, e.low, e.high);
             println!(
-                "A winner was found with score {}",
-                calc_score(winning_board, call)
+                "{:>5}  {:<22}  {}",
+                odds.board,
+                show(odds.first),
+                show(odds.last)
             );
+        }
+    }
+    Ok(())
+}
+
+fn print_report(report: &game::GameReport) {
+    for (rank, win) in report.wins.iter().enumerate() {
+        println!(
+            "{:>4}. board {} won on call {} ({}) with {:?}, score {}",
+            rank + 1,
+            win.board,
+            win.call_index,
+            win.number,
+            win.line,
+            win.score
+        );
+    }
+    for no_win in report.never_won.iter() {
+        println!(
+            "      board {} never won, {} left unmarked",
+            no_win.board, no_win.unmarked_sum
+        );
+    }
+}
+
+fn print_tournament(boards: usize, reports: &[game::GameReport]) {
+    println!("sequence  first (score)    last (score)     never won");
+    for (sequence, report) in reports.iter().enumerate() {
+        let show = |win: Option<&game::Win>| match win {
+            Some(win) => format!("{} ({})", win.board, win.score),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:>8}  {:<15}  {:<15}  {}",
+            sequence,
+            show(report.wins.first()),
+            show(report.wins.last()),
+            report.never_won.len()
+        );
+    }
+
+    println!();
+    println!("board  first  last  wins  mean winning call");
+    for standing in tournament::standings(boards, reports) {
+        println!(
+            "{:>5}  {:>5}  {:>4}  {:>4}  {}",
+            standing.board,
+            standing.first,
+            standing.last,
+            standing.wins,
+            standing
+                .mean_call
+                .map_or("-".to_string(), |c| format!("{:.1}", c))
+        );
+    }
+}
+
+// the scores of every board as it wins, by rescanning all boards after each call
+fn play_by_scanning(
+    mut boards: Vec<Array2<BingoNumber>>,
+    calls: &[usize],
+    rules: &[WinRule],
+) -> Vec<usize> {
+    let mut scores = vec![];
+    for &call in calls {
+        select(&mut boards, call);
+        while let Some((idx, winning_board, _)) = detect_winner(&boards, rules) {
+            scores.push(calc_score(winning_board, call));
             boards.remove(idx);
         }
     }
+    scores
+}
+
+fn benchmark(boards: usize, rules: &[WinRule]) {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut numbers = (0..100).collect::<Vec<usize>>();
+    let boards = (0..boards)
+        .map(|_| {
+            numbers.shuffle(&mut rng);
+            Array2::from_shape_fn((5, 5), |(r, c)| BingoNumber::new(numbers[r * 5 + c]))
+        })
+        .collect::<Vec<_>>();
+    numbers.shuffle(&mut rng);
+    let calls = numbers;
+    println!("Generated {} boards", boards.len());
+
+    let start = Instant::now();
+    let scanned = play_by_scanning(boards.clone(), &calls, rules);
+    println!("scanning: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let report = game::play(boards, &calls, rules);
+    let indexed = report.wins.iter().map(|w| w.score).collect::<Vec<_>>();
+    println!("indexed: {:?}", start.elapsed());
+
+    assert_eq!(scanned, indexed, "the two games disagree");
+}
+
+// every line of calls up to the first blank line
+fn parse_calls(input: &str) -> Result<Vec<Vec<usize>>> {
+    let sequences = input
+        .lines()
+        .take_while(|l| !l.trim().is_empty())
+        .map(|l| {
+            l.split(',')
+                .map(|c| Ok(c.trim().parse()?))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if sequences.is_empty() {
+        bail!("the input has no calls");
+    }
+    Ok(sequences)
 }
 
-fn parse_boards(input: &str) -> Vec<Array2<BingoNumber>> {
+fn parse_boards(input: &str) -> Result<Vec<Array2<BingoNumber>>> {
     let mut boards: Vec<Array2<BingoNumber>> = vec![];
     let mut lines_on_board = vec![];
-    for line in input.lines().skip(1) {
+    for line in input.lines().skip_while(|l| !l.trim().is_empty()) {
         if line.trim().is_empty() {
             if !lines_on_board.is_empty() {
-                boards.push(create_board(lines_on_board));
+                boards.push(create_board(lines_on_board)?);
                 lines_on_board = vec![];
                 continue;
             }
@@ -37,44 +227,126 @@ fn parse_boards(input: &str) -> Vec<Array2<BingoNumber>> {
             lines_on_board.push(line);
         }
     }
-    boards.push(create_board(lines_on_board));
-    boards
+    if !lines_on_board.is_empty() {
+        boards.push(create_board(lines_on_board)?);
+    }
+    Ok(boards)
 }
 
 fn calc_score(board: &Array2<BingoNumber>, winning_call: usize) -> usize {
-    // find the sum of all unmarked numbers
+    unmarked_sum(board) * winning_call
+}
+
+fn unmarked_sum(board: &Array2<BingoNumber>) -> usize {
     board
         .iter()
         .filter(|bingonum| !bingonum.selected)
         .map(|n| n.value)
         .sum::<usize>()
-        * winning_call
 }
 
-fn all_selected(lane: ArrayBase<ViewRepr<&BingoNumber>, Dim<[usize; 1]>>) -> bool {
-    for item in lane.iter() {
-        if !item.selected {
-            return false;
+fn all_selected<'a, I>(mut lane: I) -> bool
+where
+    I: Iterator<Item = &'a BingoNumber>,
+{
+    lane.all(|n| n.selected)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WinRule {
+    Rows,
+    Columns,
+    // only on square boards
+    Diagonals,
+    FourCorners,
+    FullCard,
+}
+
+impl FromStr for WinRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "rows" => Ok(WinRule::Rows),
+            "columns" => Ok(WinRule::Columns),
+            "diagonals" => Ok(WinRule::Diagonals),
+            "corners" => Ok(WinRule::FourCorners),
+            "full" => Ok(WinRule::FullCard),
+            _ => bail!(
+                "unknown rule `{}`, expected rows, columns, diagonals, corners or full",
+                s
+            ),
         }
     }
-    true
 }
 
-fn detect_winner(boards: &[Array2<BingoNumber>]) -> Option<(usize, &Array2<BingoNumber>)> {
-    for (idx, board) in boards.iter().enumerate() {
-        for axis in (0..=1).map(Axis) {
-            for lane in board.lanes(axis).into_iter() {
-                if all_selected(lane) {
-                    return Some((idx, board));
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum WinningLine {
+    Row(usize),
+    Column(usize),
+    // top left to bottom right
+    Diagonal,
+    // top right to bottom left
+    AntiDiagonal,
+    FourCorners,
+    FullCard,
+}
+
+fn winning_line(board: &Array2<BingoNumber>, rules: &[WinRule]) -> Option<WinningLine> {
+    let (height, width) = board.dim();
+    for rule in rules {
+        let found = match rule {
+            WinRule::Rows => board
+                .rows()
+                .into_iter()
+                .position(|row| all_selected(row.iter()))
+                .map(WinningLine::Row),
+            WinRule::Columns => board
+                .columns()
+                .into_iter()
+                .position(|col| all_selected(col.iter()))
+                .map(WinningLine::Column),
+            WinRule::Diagonals if height == width => {
+                if all_selected(board.diag().iter()) {
+                    Some(WinningLine::Diagonal)
+                } else if all_selected((0..height).map(|i| &board[[i, width - 1 - i]])) {
+                    Some(WinningLine::AntiDiagonal)
+                } else {
+                    None
                 }
             }
+            WinRule::Diagonals => None,
+            WinRule::FourCorners => {
+                let corners = [
+                    (0, 0),
+                    (0, width - 1),
+                    (height - 1, 0),
+                    (height - 1, width - 1),
+                ];
+                all_selected(corners.iter().map(|&(r, c)| &board[[r, c]]))
+                    .then_some(WinningLine::FourCorners)
+            }
+            WinRule::FullCard => all_selected(board.iter()).then_some(WinningLine::FullCard),
+        };
+        if found.is_some() {
+            return found;
         }
     }
-
     None
 }
 
-fn select(boards: &mut Vec<Array2<BingoNumber>>, value: usize) {
+fn detect_winner<'a>(
+    boards: &'a [Array2<BingoNumber>],
+    rules: &[WinRule],
+) -> Option<(usize, &'a Array2<BingoNumber>, WinningLine)> {
+    boards
+        .iter()
+        .enumerate()
+        .find_map(|(idx, board)| winning_line(board, rules).map(|line| (idx, board, line)))
+}
+
+fn select(boards: &mut [Array2<BingoNumber>], value: usize) {
     boards.iter_mut().for_each(|board| {
         board
             .iter_mut()
@@ -83,24 +355,35 @@ fn select(boards: &mut Vec<Array2<BingoNumber>>, value: usize) {
     });
 }
 
-fn create_board(lines: Vec<&str>) -> Array2<BingoNumber> {
-    let raw = lines
+// the size of the board is taken from its first row and how many rows it has
+fn create_board(lines: Vec<&str>) -> Result<Array2<BingoNumber>> {
+    let rows = lines
         .iter()
         .map(|l| l.trim())
         .filter(|l| !l.is_empty())
         .map(|l| {
-            l.trim()
-                .split(' ')
-                .filter(|i| !i.trim().is_empty())
-                .map(|n| BingoNumber::new(n.parse().unwrap()))
-                .collect::<Vec<_>>()
+            l.split_whitespace()
+                .map(|n| Ok(BingoNumber::new(n.parse()?)))
+                .collect::<Result<Vec<_>>>()
         })
-        .flatten()
-        .collect::<Vec<_>>();
-    Array2::from_shape_vec((5, 5), raw).unwrap()
+        .collect::<Result<Vec<_>>>()?;
+
+    let width = rows[0].len();
+    if let Some(row) = rows.iter().find(|r| r.len() != width) {
+        bail!(
+            "board rows must all be the same length, found {} and {} numbers",
+            width,
+            row.len()
+        );
+    }
+    let height = rows.len();
+    Ok(Array2::from_shape_vec(
+        (height, width),
+        rows.into_iter().flatten().collect(),
+    )?)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct BingoNumber {
     selected: bool,
     value: usize,
@@ -114,3 +397,212 @@ impl BingoNumber {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Game;
+
+    fn play(board: &str, calls: &[usize], rules: &[WinRule]) -> Option<(usize, WinningLine)> {
+        let mut boards = vec![create_board(board.lines().collect()).unwrap()];
+        for (idx, &call) in calls.iter().enumerate() {
+            select(&mut boards, call);
+            if let Some((_, _, line)) = detect_winner(&boards, rules) {
+                return Some((idx, line));
+            }
+        }
+        None
+    }
+
+    const EXAMPLE: &str =
+        "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1\n\n\
+            22 13 17 11  0\n 8  2 23  4 24\n21  9 14 16  7\n 6 10  3 18  5\n 1 12 20 15 19\n\n\
+             3 15  0  2 22\n 9 18 13 17  5\n19  8  7 25 23\n20 11 10 24  4\n14 21 16 12  6\n\n\
+            14 21 17 24  4\n10 16 15  9 19\n18  8 23 26 20\n22 11 13  6  5\n 2  0 12  3  7";
+
+    fn example_calls() -> Vec<usize> {
+        EXAMPLE
+            .lines()
+            .next()
+            .unwrap()
+            .split(',')
+            .map(|c| c.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn problems_and_tournaments() {
+        let input = "1,2,3,2\n3,1,4\n\n1 2\n3 1\n\n1 4\n2 3";
+        let sequences = parse_calls(input).unwrap();
+        assert_eq!(vec![vec![1, 2, 3, 2], vec![3, 1, 4]], sequences);
+        let boards = parse_boards(input).unwrap();
+        assert_eq!(2, boards.len());
+
+        use validation::Problem::*;
+        assert_eq!(
+            vec![
+                DuplicateOnBoard { board: 0, value: 1 },
+                RepeatedCall {
+                    sequence: 0,
+                    value: 2,
+                    first: 1,
+                    again: 3
+                },
+                NeverCalled {
+                    sequence: 0,
+                    board: 1,
+                    values: vec![4]
+                },
+                NeverCalled {
+                    sequence: 1,
+                    board: 0,
+                    values: vec![2]
+                },
+                NeverCalled {
+                    sequence: 1,
+                    board: 1,
+                    values: vec![2]
+                },
+            ],
+            validation::validate(&sequences, &boards)
+        );
+
+        let reports = tournament::play(&boards, &sequences, &[WinRule::Rows]);
+        let winners = |r: &game::GameReport| r.wins.iter().map(|w| w.board).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1], winners(&reports[0]));
+        assert_eq!(vec![0, 1], winners(&reports[1]));
+        let standings = tournament::standings(2, &reports);
+        assert_eq!(
+            (2, 0, 2),
+            (standings[0].first, standings[0].last, standings[0].wins)
+        );
+        assert_eq!(Some(1.0), standings[0].mean_call);
+        assert_eq!(Some(2.0), standings[1].mean_call);
+    }
+
+    #[test]
+    fn indexed_game_matches_scanning() {
+        let input = EXAMPLE;
+        let calls = example_calls();
+        use WinRule::*;
+        for rules in [
+            vec![Rows, Columns],
+            vec![Diagonals],
+            vec![FourCorners, FullCard],
+            vec![FullCard],
+        ] {
+            let boards = parse_boards(input).unwrap();
+            let scanned = play_by_scanning(boards.clone(), &calls, &rules);
+
+            let mut game = Game::new(boards, &rules);
+            let mut indexed = vec![];
+            for &call in calls.iter() {
+                for (idx, line) in game.call(call) {
+                    assert_eq!(Some(line), winning_line(&game.boards[idx], &rules));
+                    indexed.push(calc_score(&game.boards[idx], call));
+                }
+            }
+            assert_eq!(scanned, indexed);
+        }
+        let scores = play_by_scanning(parse_boards(input).unwrap(), &calls, &[Rows, Columns]);
+        assert_eq!(vec![4512, 2192, 1924], scores);
+    }
+
+    #[test]
+    fn report_ranks_every_board() {
+        let calls = &example_calls()[..14];
+        let report = game::play(parse_boards(EXAMPLE).unwrap(), calls, &[WinRule::Rows]);
+        let win = |board, call_index, number, row, score| game::Win {
+            board,
+            call_index,
+            number,
+            line: WinningLine::Row(row),
+            score,
+        };
+        assert_eq!(
+            vec![win(2, 11, 24, 0, 4512), win(0, 13, 16, 2, 2192)],
+            report.wins
+        );
+        assert_eq!(
+            vec![game::NoWin {
+                board: 1,
+                unmarked_sum: 161,
+            }],
+            report.never_won
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(0, json["wins"][0]["line"]["row"]);
+        assert_eq!(4512, json["wins"][0]["score"]);
+        assert_eq!(1, json["never_won"][0]["board"]);
+    }
+
+    #[test]
+    fn simulation_is_seeded() {
+        let boards = parse_boards(EXAMPLE).unwrap();
+        let calls = example_calls();
+        let rules = [WinRule::Rows, WinRule::Columns];
+        // the same down to the last bit however many threads share the trials
+        let with_threads = |threads, seed| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| montecarlo::estimate(&boards, &calls, &rules, 400, seed))
+        };
+        let odds = with_threads(1, 9);
+        for threads in [2, 3, 8] {
+            assert_eq!(odds, with_threads(threads, 9));
+        }
+        assert_ne!(odds, with_threads(4, 10));
+
+        // every shuffle of these calls has a first and a last winner
+        let total = |f: fn(&montecarlo::BoardOdds) -> f64| odds.iter().map(f).sum::<f64>();
+        assert!((total(|o| o.first.mean) - 1.0).abs() < 1e-9);
+        assert!((total(|o| o.last.mean) - 1.0).abs() < 1e-9);
+        for o in odds.iter() {
+            assert!(o.first.low <= o.first.mean && o.first.mean <= o.first.high);
+            assert!(o.first.high - o.first.low < 0.15);
+        }
+    }
+
+    #[test]
+    fn board_size_is_inferred() {
+        let boards = parse_boards("1,2\n\n1 2 3 4\n5 6 7 8\n\n 9 10\n11 12\n13 14").unwrap();
+        assert_eq!((2, 4), boards[0].dim());
+        assert_eq!((3, 2), boards[1].dim());
+        assert!(parse_boards("1\n\n1 2 3\n4 5").is_err());
+    }
+
+    #[test]
+    fn win_rules() {
+        let square = "1 2 3\n4 5 6\n7 8 9";
+        let calls = [3, 1, 5, 9, 7, 2, 4, 6, 8];
+        use WinRule::*;
+        assert_eq!(None, play(square, &calls[..5], &[Rows, Columns]));
+        assert_eq!(
+            Some((3, WinningLine::Diagonal)),
+            play(square, &calls, &[Diagonals])
+        );
+        assert_eq!(
+            Some((4, WinningLine::FourCorners)),
+            play(square, &calls, &[FourCorners])
+        );
+        assert_eq!(
+            Some((5, WinningLine::Row(0))),
+            play(square, &calls, &[Rows, FullCard])
+        );
+        assert_eq!(
+            Some((8, WinningLine::FullCard)),
+            play(square, &calls, &[FullCard])
+        );
+
+        // diagonals only count on square boards
+        let wide = "1 2 3 4\n5 6 7 8";
+        assert_eq!(None, play(wide, &[1, 6], &[Diagonals]));
+        assert_eq!(
+            Some((3, WinningLine::FourCorners)),
+            play(wide, &[1, 4, 5, 8], &[FourCorners])
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use ndarray::Array2;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+use crate::{game, BingoNumber, WinRule};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    // the 95% confidence interval
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardOdds {
+    pub board: usize,
+    pub first: Estimate,
+    pub last: Estimate,
+}
+
+/*
+    Plays `trials` games with the calls shuffled into a random order each time,
+    and estimates how likely every board is to win first and to win last. When
+    several boards win on the same call they share the credit equally.
+
+    Each trial draws from its own stream of the seeded generator, and the
+    credit is tallied as whole counts of wins by the size of the tie, which
+    add up the same in any order. Only once they are all in are they turned
+    into floats, so the result depends on the seed and not on how the trials
+    are spread over threads.
+*/
+pub fn estimate(
+    boards: &[Array2<BingoNumber>],
+    calls: &[usize],
+    rules: &[WinRule],
+    trials: usize,
+    seed: u64,
+) -> Vec<BoardOdds> {
+    let (first, last) = (0..trials)
+        .into_par_iter()
+        .map(|trial| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(trial as u64);
+            let mut order = calls.to_vec();
+            order.shuffle(&mut rng);
+
+            let report = game::play(boards.to_vec(), &order, rules);
+            match (report.wins.first(), report.wins.last()) {
+                (Some(f), Some(l)) => (
+                    share(&report.wins, f.call_index),
+                    share(&report.wins, l.call_index),
+                ),
+                _ => (vec![], vec![]),
+            }
+        })
+        .fold(
+            || (Tally::new(boards.len()), Tally::new(boards.len())),
+            |(mut first, mut last), (f, l)| {
+                first.add(&f);
+                last.add(&l);
+                (first, last)
+            },
+        )
+        .reduce(
+            || (Tally::new(boards.len()), Tally::new(boards.len())),
+            |(mut first, mut last), (f, l)| {
+                first.merge(&f);
+                last.merge(&l);
+                (first, last)
+            },
+        );
+
+    (0..boards.len())
+        .map(|board| BoardOdds {
+            board,
+            first: first.estimate(board, trials),
+            last: last.estimate(board, trials),
+        })
+        .collect()
+}
+
+// the boards that won on the call, each with how many won on it
+fn share(wins: &[game::Win], call_index: usize) -> Vec<(usize, usize)> {
+    let winners = wins.iter().filter(|w| w.call_index == call_index);
+    let count = winners.clone().count();
+    winners.map(|win| (win.board, count)).collect()
+}
+
+// For each board, how many trials it won in a tie of each size, where a tie of
+// one is an outright win. A tie of k is worth 1/k of a win. Only the sizes a
+// board has actually tied at are kept.
+#[derive(Debug, Clone)]
+struct Tally {
+    by_tie_size: Vec<BTreeMap<usize, u64>>,
+}
+
+impl Tally {
+    fn new(boards: usize) -> Tally {
+        Tally {
+            by_tie_size: vec![BTreeMap::new(); boards],
+        }
+    }
+
+    fn add(&mut self, winners: &[(usize, usize)]) {
+        for &(board, k) in winners {
+            *self.by_tie_size[board].entry(k).or_default() += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &Tally) {
+        for (mine, theirs) in self.by_tie_size.iter_mut().zip(other.by_tie_size.iter()) {
+            for (&k, &count) in theirs {
+                *mine.entry(k).or_default() += count;
+            }
+        }
+    }
+
+    // normal approximation of the 95% interval, from the sample variance
+    fn estimate(&self, board: usize, trials: usize) -> Estimate {
+        if trials == 0 {
+            return Estimate {
+                mean: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+        // the sums of each trial's credit and its square, always in the same order
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        for (&k, &count) in self.by_tie_size[board].iter() {
+            let credit = 1.0 / k as f64;
+            sum += count as f64 * credit;
+            sum_sq += count as f64 * credit * credit;
+        }
+        let n = trials as f64;
+        let mean = sum / n;
+        let variance = match trials {
+            1 => 0.0,
+            _ => ((sum_sq - n * mean * mean) / (n - 1.0)).max(0.0),
+        };
+        let margin = 1.96 * (variance / n).sqrt();
+        Estimate {
+            mean,
+            low: (mean - margin).max(0.0),
+            high: (mean + margin).min(1.0),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use ndarray::Array2;
+
+use crate::{
+    game::{self, GameReport},
+    BingoNumber, WinRule,
+};
+
+// How one board fared over every sequence of a tournament.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub board: usize,
+    // sequences in which the board won first, and last
+    pub first: usize,
+    pub last: usize,
+    // sequences in which it won at all
+    pub wins: usize,
+    // the mean index of the call that won it, over those wins
+    pub mean_call: Option<f64>,
+}
+
+// Each sequence is played against fresh copies of the boards.
+pub fn play(
+    boards: &[Array2<BingoNumber>],
+    sequences: &[Vec<usize>],
+    rules: &[WinRule],
+) -> Vec<GameReport> {
+    sequences
+        .iter()
+        .map(|calls| game::play(boards.to_vec(), calls, rules))
+        .collect()
+}
+
+pub fn standings(boards: usize, reports: &[GameReport]) -> Vec<Standing> {
+    let mut standings = (0..boards)
+        .map(|board| Standing {
+            board,
+            first: 0,
+            last: 0,
+            wins: 0,
+            mean_call: None,
+        })
+        .collect::<Vec<_>>();
+    let mut call_sums = vec![0; boards];
+
+    for report in reports {
+        if let (Some(first), Some(last)) = (report.wins.first(), report.wins.last()) {
+            standings[first.board].first += 1;
+            standings[last.board].last += 1;
+        }
+        for win in report.wins.iter() {
+            standings[win.board].wins += 1;
+            call_sums[win.board] += win.call_index;
+        }
+    }
+    for (standing, sum) in standings.iter_mut().zip(call_sums) {
+        if standing.wins > 0 {
+            standing.mean_call = Some(sum as f64 / standing.wins as f64);
+        }
+    }
+    standings
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use ndarray::Array2;
+
+use crate::BingoNumber;
+
+// Something in the input that is legal to play but probably a mistake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    // a number printed more than once on the same board
+    DuplicateOnBoard {
+        board: usize,
+        value: usize,
+    },
+    // a number called again after it was first called at `first`
+    RepeatedCall {
+        sequence: usize,
+        value: usize,
+        first: usize,
+        again: usize,
+    },
+    // numbers on a board that a sequence never calls
+    NeverCalled {
+        sequence: usize,
+        board: usize,
+        values: Vec<usize>,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::DuplicateOnBoard { board, value } => {
+                write!(f, "board {} has {} more than once", board, value)
+            }
+            Problem::RepeatedCall {
+                sequence,
+                value,
+                first,
+                again,
+            } => write!(
+                f,
+                "sequence {} calls {} at {} and again at {}",
+                sequence, value, first, again
+            ),
+            Problem::NeverCalled {
+                sequence,
+                board,
+                values,
+            } => write!(
+                f,
+                "sequence {} never calls {:?} from board {}",
+                sequence, values, board
+            ),
+        }
+    }
+}
+
+pub fn validate(sequences: &[Vec<usize>], boards: &[Array2<BingoNumber>]) -> Vec<Problem> {
+    let mut problems = vec![];
+    for (board, numbers) in boards.iter().enumerate() {
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for n in numbers.iter() {
+            if !seen.insert(n.value) && reported.insert(n.value) {
+                problems.push(Problem::DuplicateOnBoard {
+                    board,
+                    value: n.value,
+                });
+            }
+        }
+    }
+
+    for (sequence, calls) in sequences.iter().enumerate() {
+        let mut first_call = HashMap::new();
+        for (again, &value) in calls.iter().enumerate() {
+            if let Some(&first) = first_call.get(&value) {
+                problems.push(Problem::RepeatedCall {
+                    sequence,
+                    value,
+                    first,
+                    again,
+                });
+            } else {
+                first_call.insert(value, again);
+            }
+        }
+
+        for (board, numbers) in boards.iter().enumerate() {
+            let mut values = numbers
+                .iter()
+                .map(|n| n.value)
+                .filter(|v| !first_call.contains_key(v))
+                .collect::<Vec<_>>();
+            values.sort_unstable();
+            values.dedup();
+            if !values.is_empty() {
+                problems.push(Problem::NeverCalled {
+                    sequence,
+                    board,
+                    values,
+                });
+            }
+        }
+    }
+    problems
+}
@@ -0,0 +1,159 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use image::{Rgb, RgbImage};
+
+use crate::map::{Bounds, Map};
+
+// Images with more pixels than this are refused rather than allocated.
+const MAX_PIXELS: u64 = 1 << 26;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+// The colour scale runs through these, from one line up to the most overlaps.
+const STOPS: [[u8; 3]; 4] = [[255, 237, 160], [254, 178, 76], [240, 59, 32], [128, 0, 38]];
+
+// 3x5 digits for the legend's labels, one row of three bits per line.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/*
+    Writes the map as a heatmap cropped to the covered cells, each drawn as a
+    `scale` x `scale` square, with a legend for the colour scale underneath.
+    The format follows the extension of the path, png or bmp.
+*/
+pub fn export(map: &Map, path: &Path, scale: u32) -> Result<()> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    if !matches!(extension.as_deref(), Some("png" | "bmp")) {
+        bail!("can only export to .png or .bmp, not {}", path.display());
+    }
+    if scale == 0 {
+        bail!("the scale must be at least 1");
+    }
+    let Some(bounds) = Bounds::of_points(map.covered().map(|(p, _)| p)) else {
+        bail!("no line covers any point, so there is nothing to draw");
+    };
+    let most = map.covered().map(|(_, c)| c).max().unwrap_or(1);
+
+    let (width, height) = (bounds.width() as u64, bounds.height() as u64);
+    let pixels = width
+        .checked_mul(scale as u64)
+        .zip(height.checked_mul(scale as u64))
+        .and_then(|(w, h)| w.checked_mul(h));
+    if pixels.is_none_or(|p| p > MAX_PIXELS) {
+        bail!(
+            "a {}x{} map at scale {} is too large to draw",
+            width,
+            height,
+            scale
+        );
+    }
+    let (width, height) = (width as u32 * scale, height as u32 * scale);
+
+    let legend = Legend::new(width, most);
+    let mut img = RgbImage::from_pixel(width.max(legend.width), height + legend.height, BACKGROUND);
+    for (p, count) in map.covered() {
+        let colour = colour(shade(count, most));
+        let (x, y) = (
+            (p.x - bounds.min.x) as u32 * scale,
+            (p.y - bounds.min.y) as u32 * scale,
+        );
+        fill(&mut img, x, y, scale, scale, colour);
+    }
+    legend.draw(&mut img, height, most);
+
+    img.save(path)?;
+    Ok(())
+}
+
+// where a count sits on the colour scale, from 0 for a single line to 1 for the most
+fn shade(count: usize, most: usize) -> f64 {
+    match most {
+        1 => 1.0,
+        _ => (count - 1) as f64 / (most - 1) as f64,
+    }
+}
+
+// linear interpolation between the neighbouring stops
+fn colour(t: f64) -> Rgb<u8> {
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f64;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+    Rgb([0, 1, 2].map(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * f).round() as u8))
+}
+
+fn fill(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, colour: Rgb<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            img.put_pixel(px, py, colour);
+        }
+    }
+}
+
+// A bar running through the colour scale, labelled with 1 and the most overlaps.
+struct Legend {
+    // the size of one pixel of a digit, and of the margins
+    unit: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Legend {
+    fn new(map_width: u32, most: usize) -> Legend {
+        let unit = (map_width / 100).clamp(1, 4);
+        let label = most.to_string().len() as u32 * 4;
+        Legend {
+            unit,
+            // room for both labels side by side
+            width: (label + 8) * unit,
+            // margin, bar, margin, labels, margin
+            height: (2 + 4 + 2 + 5 + 2) * unit,
+        }
+    }
+
+    fn draw(&self, img: &mut RgbImage, top: u32, most: usize) {
+        let u = self.unit;
+        let bar_width = img.width() - 4 * u;
+        for i in 0..bar_width {
+            let t = i as f64 / (bar_width - 1).max(1) as f64;
+            fill(img, 2 * u + i, top + 2 * u, 1, 4 * u, colour(t));
+        }
+
+        let labels_top = top + 8 * u;
+        self.text(img, 2 * u, labels_top, "1");
+        // right aligned with the end of the bar
+        let most = most.to_string();
+        let label_width = (most.len() as u32 * 4 - 1) * u;
+        self.text(img, img.width() - 2 * u - label_width, labels_top, &most);
+    }
+
+    fn text(&self, img: &mut RgbImage, left: u32, top: u32, digits: &str) {
+        let u = self.unit;
+        for (n, d) in digits.bytes().enumerate() {
+            let glyph = DIGITS[(d - b'0') as usize];
+            let left = left + n as u32 * 4 * u;
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        let (x, y) = (left + col * u, top + row as u32 * u);
+                        fill(img, x, y, u, u, Rgb([0, 0, 0]));
+                    }
+                }
+            }
+        }
+    }
+}
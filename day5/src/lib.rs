@@ -0,0 +1,338 @@
+pub mod heatmap;
+pub mod map;
+pub mod overlap;
+pub mod query;
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+// Coordinates are kept within this so that spans between them can't overflow.
+pub const COORD_LIMIT: isize = i32::MAX as isize;
+
+// one line per row of the input, like `0,9 -> 5,9`
+pub fn parse_lines(input: &str) -> Result<Vec<Line>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(n, l)| l.parse::<Line>().with_context(|| format!("line {}", n + 1)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vec2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl FromStr for Vec2 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| anyhow!("expected `x,y`, found `{}`", s))?;
+        let coord = |c: &str| -> Result<isize> {
+            let c = c.trim().parse::<isize>()?;
+            if c.abs() > COORD_LIMIT {
+                bail!("coordinate {} is beyond ±{}", c, COORD_LIMIT);
+            }
+            Ok(c)
+        };
+        Ok(Vec2 {
+            x: coord(x)?,
+            y: coord(y)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Line {
+    pub fn is_hori_or_vert(&self) -> bool {
+        self.start.x == self.end.x || self.start.y == self.end.y
+    }
+
+    // whether the line passes through the point
+    pub fn contains(&self, p: Vec2) -> bool {
+        let (x, y) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        let (px, py) = (p.x - self.start.x, p.y - self.start.y);
+        // collinear, in i128 as the products of spans can overflow isize
+        let collinear = x as i128 * py as i128 == y as i128 * px as i128;
+        collinear
+            && (self.start.x.min(self.end.x)..=self.start.x.max(self.end.x)).contains(&p.x)
+            && (self.start.y.min(self.end.y)..=self.start.y.max(self.end.y)).contains(&p.y)
+    }
+
+    // The direction of one step between lattice points, e.g. (3, -2) for a
+    // line from 0,0 to 6,-4.
+    pub fn direction(&self) -> (isize, isize) {
+        let x = self.end.x - self.start.x;
+        let y = self.end.y - self.start.y;
+        match gcd(x.abs(), y.abs()) {
+            0 => (0, 0),
+            d => (x / d, y / d),
+        }
+    }
+
+    // how many lattice points the line passes through, without visiting them
+    pub fn point_count(&self) -> usize {
+        let (x, y) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        gcd(x.abs(), y.abs()) as usize + 1
+    }
+
+    // every lattice point the line passes through, from start to end
+    pub fn points(&self) -> impl Iterator<Item = Vec2> {
+        let (xdir, ydir) = self.direction();
+        let steps = match (xdir, ydir) {
+            (0, 0) => 0,
+            (0, _) => (self.end.y - self.start.y) / ydir,
+            _ => (self.end.x - self.start.x) / xdir,
+        };
+        let start = self.start;
+        (0..=steps).map(move |i| Vec2 {
+            x: start.x + i * xdir,
+            y: start.y + i * ydir,
+        })
+    }
+}
+
+fn gcd(a: isize, b: isize) -> isize {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.start, self.end)
+    }
+}
+
+impl FromStr for Line {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once(" -> ")
+            .ok_or_else(|| anyhow!("expected `x,y -> x,y`, found `{}`", s))?;
+        Ok(Line {
+            start: start.parse()?,
+            end: end.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::Map;
+
+    const EXAMPLE: &str = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n\
+                           6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+
+    fn parse(input: &str) -> Vec<Line> {
+        input.lines().map(|l| l.parse().unwrap()).collect()
+    }
+
+    fn dangerous(map: &mut Map, lines: &[Line]) -> usize {
+        for l in lines {
+            map.add_line(l).unwrap();
+        }
+        map.dangerous()
+    }
+
+    #[test]
+    fn dense_and_sparse_agree() {
+        let lines = parse(EXAMPLE);
+        let bounds = map::Bounds::of_lines(&lines).unwrap();
+        assert_eq!(12, dangerous(&mut Map::dense(bounds), &lines));
+        assert_eq!(12, dangerous(&mut Map::sparse(bounds), &lines));
+
+        // shifted far into the negatives with one point far the other way,
+        // which only a sparse map can hold
+        let shift = |p: Vec2| Vec2 {
+            x: p.x - 1_000_000_000,
+            y: p.y - 1_000_000_000,
+        };
+        let mut shifted = lines
+            .iter()
+            .filter(|l| l.is_hori_or_vert())
+            .map(|l| Line {
+                start: shift(l.start),
+                end: shift(l.end),
+            })
+            .collect::<Vec<_>>();
+        shifted.extend(parse("1000000000,1000000000 -> 1000000000,1000000000"));
+        let mut map = Map::for_lines(&shifted);
+        assert_eq!(5, dangerous(&mut map, &shifted));
+        assert_eq!(2, map.get(shift(Vec2 { x: 0, y: 9 })));
+    }
+
+    #[test]
+    fn lattice_points() {
+        let points = |l: &str| {
+            l.parse::<Line>()
+                .unwrap()
+                .points()
+                .map(|p| (p.x, p.y))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![(0, 0), (3, -2), (6, -4)], points("0,0 -> 6,-4"));
+        assert_eq!(vec![(0, 0), (1, 2)], points("0,0 -> 1,2"));
+        assert_eq!(vec![(4, 1), (3, 1), (2, 1)], points("4,1 -> 2,1"));
+        assert_eq!(vec![(2, 3), (1, 4)], points("2,3 -> 1,4"));
+        assert_eq!(vec![(5, 5)], points("5,5 -> 5,5"));
+        for l in ["0,0 -> 6,-4", "4,1 -> 2,1", "5,5 -> 5,5"] {
+            assert_eq!(points(l).len(), l.parse::<Line>().unwrap().point_count());
+        }
+        let long = "0,0 -> 2000000000,0".parse::<Line>().unwrap();
+        assert_eq!(2_000_000_001, long.point_count());
+
+        // a steep and a shallow line crossing at a shared lattice point
+        let lines = parse("0,0 -> 2,6\n0,6 -> 2,0\n0,3 -> 9,3");
+        let mut map = Map::for_lines(&lines);
+        assert_eq!(1, dangerous(&mut map, &lines));
+        assert_eq!(3, map.get(Vec2 { x: 1, y: 3 }));
+    }
+
+    #[test]
+    fn queries() {
+        let lines = parse_lines(EXAMPLE).unwrap();
+        let mut map = Map::for_lines(&lines);
+        dangerous(&mut map, &lines);
+
+        let p = Vec2 { x: 4, y: 4 };
+        assert_eq!(3, map.get(p));
+        let through = query::lines_through(&lines, p)
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["8,0 -> 0,8", "9,4 -> 3,4", "0,0 -> 8,8"], through);
+        assert!(query::lines_through(&lines, Vec2 { x: 9, y: 9 }).is_empty());
+
+        let rect = map::Bounds::of_points([Vec2 { x: 4, y: 4 }, Vec2 { x: 0, y: 0 }]).unwrap();
+        assert_eq!(3, map.dangerous_in(rect));
+        assert_eq!(12, map.dangerous_in(map::Bounds::of_lines(&lines).unwrap()));
+        assert_eq!(
+            vec![(Vec2 { x: 4, y: 4 }, 3), (Vec2 { x: 6, y: 4 }, 3)],
+            map.at_least(3)
+        );
+        assert_eq!(12, map.at_least(2).len());
+    }
+
+    #[test]
+    fn overlaps() {
+        let overlap = |a: &str, b: &str| {
+            let a = a.parse::<Line>().unwrap();
+            let b = b.parse::<Line>().unwrap();
+            let forwards = overlap::overlap(&a, &b);
+            let backwards = overlap::overlap(&b, &a);
+            // the same points, though a shared segment may run the other way
+            assert_eq!(forwards.is_some(), backwards.is_some());
+            forwards.map(|o| o.to_string())
+        };
+        assert_eq!(Some("2,2".to_string()), overlap("0,0 -> 4,4", "0,4 -> 4,0"));
+        assert_eq!(None, overlap("0,0 -> 1,1", "0,1 -> 1,0"));
+        assert_eq!(None, overlap("0,0 -> 1,1", "2,2 -> 3,3"));
+        assert_eq!(None, overlap("0,0 -> 2,0", "0,1 -> 2,1"));
+        assert_eq!(Some("2,2".to_string()), overlap("0,0 -> 2,2", "4,4 -> 2,2"));
+        assert_eq!(
+            Some("3,2 -> 6,4".to_string()),
+            overlap("0,0 -> 6,4", "9,6 -> 3,2")
+        );
+        assert_eq!(Some("3,1".to_string()), overlap("3,1 -> 3,1", "0,0 -> 6,2"));
+        assert_eq!(None, overlap("3,1 -> 3,1", "0,0 -> 6,3"));
+
+        let lines = parse_lines(EXAMPLE).unwrap();
+        assert_eq!(12, overlap::dangerous(&overlap::crossings(&lines)));
+    }
+
+    #[test]
+    fn overlaps_match_the_grid() {
+        // a small LCG, so lines at every slope land on top of each other
+        let mut seed = 12345u64;
+        let mut next = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % n) as isize - 6
+        };
+        for _ in 0..200 {
+            let lines = (0..8)
+                .map(|_| Line {
+                    start: Vec2 {
+                        x: next(13),
+                        y: next(13),
+                    },
+                    end: Vec2 {
+                        x: next(13),
+                        y: next(13),
+                    },
+                })
+                .collect::<Vec<_>>();
+            let mut map = Map::for_lines(&lines);
+            let expected = dangerous(&mut map, &lines);
+            let crossings = overlap::crossings(&lines);
+            assert_eq!(expected, overlap::dangerous(&crossings), "{:?}", lines);
+            for c in crossings {
+                let shared = match c.overlap {
+                    overlap::Overlap::Point(p) => vec![p],
+                    overlap::Overlap::Segment(l) => l.points().collect(),
+                };
+                assert!(shared.iter().all(|&p| map.get(p) >= 2));
+            }
+        }
+    }
+
+    #[test]
+    fn heatmap() {
+        let lines = parse("-2,0 -> 2,0\n0,-1 -> 0,1\n0,0 -> 0,0");
+        let mut map = Map::for_lines(&lines);
+        dangerous(&mut map, &lines);
+        let path = std::env::temp_dir().join("day5-heatmap-test.png");
+        heatmap::export(&map, &path, 2).unwrap();
+
+        let img = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(&path).unwrap();
+        // a 5x3 cross at 2 pixels a point, widened to fit the legend beneath
+        assert_eq!(12, img.width());
+        assert_eq!(6 + 15, img.height());
+        let white = image::Rgb([255, 255, 255]);
+        assert_eq!(white, *img.get_pixel(0, 0));
+        // one line at the ends, the most overlaps in the middle
+        assert_eq!(image::Rgb([255, 237, 160]), *img.get_pixel(0, 2));
+        assert_eq!(image::Rgb([128, 0, 38]), *img.get_pixel(5, 3));
+
+        assert!(heatmap::export(&map, std::path::Path::new("map.gif"), 1).is_err());
+        assert!(heatmap::export(&map, &path, 0).is_err());
+
+        // opposite corners of the coordinate range, too large to even count the pixels
+        let lines = parse("-2147483647,-2147483647 -> -2147483647,-2147483647\n2147483647,2147483647 -> 2147483647,2147483647");
+        let mut map = Map::for_lines(&lines);
+        dangerous(&mut map, &lines);
+        assert!(heatmap::export(&map, &path, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert!("1,2 -> 3".parse::<Line>().is_err());
+        assert!("1,2 3,4".parse::<Line>().is_err());
+        assert!("0,0 -> 99999999999,0".parse::<Line>().is_err());
+
+        let lines = parse("0,0 -> 2,2");
+        let mut map = Map::for_lines(&lines);
+        assert!(map.add_line(&parse("0,0 -> 3,0")[0]).is_err());
+    }
+}
@@ -1,160 +1,155 @@
-use std::{num::ParseIntError, ops::RangeInclusive, str::FromStr};
-
-use bmp::{px, Image, Pixel};
-use ndarray::Array2;
-
-fn main() {
-    let lines = include_str!("input")
-        .lines()
-        .map(|l| l.parse::<Line>().unwrap())
-        .collect::<Vec<_>>();
-
-    let mut map = Map::new(1000, 1000);
-
-    for l in lines.iter().filter(|l| l.is_hori_or_vert()) {
-        map.add_line(l);
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+
+use day5::{
+    heatmap,
+    map::{Bounds, Map},
+    overlap, parse_lines, query, Line, Vec2,
+};
+
+// Lines passing through more points than this are never painted onto a map.
+const MAX_PAINTED: u64 = 1 << 26;
+
+/*
+    Usage: day5 [--render] [--export <path.png|path.bmp> [--scale <n>]]
+                [--at <x,y>] [--dangerous-in <x,y> <x,y>] [--at-least <n>]
+                [--crossings]
+
+    `--render` prints the map, `--export` draws it as a heatmap of how many
+    lines cross each point, cropped to the covered points and with every point
+    drawn `--scale` pixels wide. The map spans the bounding box of the lines,
+    which may be anywhere within ±COORD_LIMIT.
+
+    The queries look at the map with every line on it: `--at` shows how many
+    and which lines cross a point, `--dangerous-in` counts the points crossed
+    by two or more lines in the rectangle between two corners, and
+    `--at-least` lists the points crossed by at least that many lines.
+
+    `--crossings` lists every pair of lines that share a point or a segment,
+    worked out from the lines themselves rather than the map, and counts the
+    dangerous points from those alone. Unless one of the options above needs
+    the map, it isn't painted at all, and neither is it when the lines pass
+    through more than MAX_PAINTED points, which only `--at` and `--crossings`
+    can work without.
+*/
+fn main() -> Result<()> {
+    let mut render = false;
+    let mut export = None;
+    let mut scale = 1;
+    let mut at = None;
+    let mut rect = None;
+    let mut threshold = None;
+    let mut show_crossings = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--render" => render = true,
+            "--export" => export = Some(PathBuf::from(value()?)),
+            "--scale" => scale = value()?.parse()?,
+            "--at" => at = Some(value()?.parse::<Vec2>()?),
+            "--dangerous-in" => {
+                let corners = [value()?.parse::<Vec2>()?, value()?.parse()?];
+                rect = Bounds::of_points(corners);
+            }
+            "--at-least" => threshold = Some(value()?.parse()?),
+            "--crossings" => show_crossings = true,
+            _ => bail!("unknown argument `{}`", arg),
+        }
     }
-    println!(
-        "part 1: {:#?}",
-        map.map.iter().filter(|&&coord| coord >= 2).count()
-    );
 
-    for l in lines.iter().filter(|l| !l.is_hori_or_vert()) {
-        map.add_line(l);
+    let lines = parse_lines(include_str!("input"))?;
+    let wants_map = render || export.is_some() || rect.is_some() || threshold.is_some();
+    let points = lines.iter().map(|l| l.point_count() as u64).sum::<u64>();
+    if wants_map && points > MAX_PAINTED {
+        bail!(
+            "the lines pass through {} points, too many to paint onto a map",
+            points
+        );
     }
-    println!(
-        "part 2: {:#?}",
-        map.map.iter().filter(|&&coord| coord >= 2).count()
-    );
-
-    // map.render_to_bmp();
-}
 
-struct Map {
-    map: Array2<isize>,
-}
-
-impl Map {
-    fn new(x: usize, y: usize) -> Map {
-        Map {
-            map: Array2::<isize>::zeros((x, y)),
+    // without a map to paint, the parts are counted from the crossings instead
+    let crossings = (show_crossings || points > MAX_PAINTED).then(|| overlap::crossings(&lines));
+    let map = match (wants_map, &crossings) {
+        (false, Some(crossings)) => {
+            let straight = crossings
+                .iter()
+                .filter(|c| lines[c.a].is_hori_or_vert() && lines[c.b].is_hori_or_vert())
+                .copied()
+                .collect::<Vec<_>>();
+            println!("part 1: {:#?}", overlap::dangerous(&straight));
+            println!("part 2: {:#?}", overlap::dangerous(crossings));
+            None
         }
-    }
+        _ => Some(paint(&lines)?),
+    };
 
-    fn add_line(&mut self, line: &Line) {
-        if line.is_hori_or_vert() {
-            // iterate the line
-            for xpos in create_range(line.start.x, line.end.x) {
-                for ypos in create_range(line.start.y, line.end.y) {
-                    self.map[[xpos as usize, ypos as usize]] += 1;
-                }
-            }
-        } else {
-            // if its diagonal, we need to find which direction
-            let (xdir, ydir) = line.get_direction();
-            let mut current = line.start;
-            while current != line.end {
-                self.map[[current.x as usize, current.y as usize]] += 1;
-                current.x += xdir;
-                current.y += ydir;
-            }
-            self.map[[current.x as usize, current.y as usize]] += 1;
+    if let Some(map) = &map {
+        if render {
+            map.render();
         }
-    }
-
-    fn render(&mut self) {
-        for y in 0..self.map.shape()[0] {
-            for x in 0..self.map.shape()[1] {
-                let val = self.map[[x as usize, y as usize]];
-                if val == 0 {
-                    print!(".")
-                } else {
-                    print!("{}", val)
-                }
-            }
-            println!();
+        if let Some(path) = &export {
+            heatmap::export(map, path, scale)?;
         }
     }
 
-    fn render_to_bmp(&mut self) {
-        let mut img = Image::new(1000, 1000);
-
-        for ((x, y), val) in self.map.indexed_iter() {
-            img.set_pixel(
-                x as u32,
-                y as u32,
-                match val {
-                    0 => px!(255, 255, 255),
-                    1 => px!(255, 0, 0),
-                    2 => px!(0, 255, 255),
-                    3 => px!(0, 0, 255),
-                    4 => px!(0, 255, 0),
-                    5 => px!(255, 0, 255),
-                    _ => px!(0, 0, 0),
-                },
-            )
+    if let Some(p) = at {
+        let through = query::lines_through(&lines, p);
+        println!("{} is crossed by {} lines:", p, through.len());
+        for line in through {
+            println!("    {}", line);
         }
-        img.save("output.bmp").unwrap();
     }
-}
-
-fn create_range(start: isize, end: isize) -> RangeInclusive<isize> {
-    match start > end {
-        true => end..=start,
-        false => start..=end,
+    if let (Some(rect), Some(map)) = (rect, &map) {
+        println!(
+            "{} dangerous points from {} to {}",
+            map.dangerous_in(rect),
+            rect.min,
+            rect.max
+        );
     }
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Vec2 {
-    x: isize,
-    y: isize,
-}
-
-impl PartialEq for Vec2 {
-    fn eq(&self, other: &Vec2) -> bool {
-        self.x == other.x && self.y == other.y
+    if let (Some(threshold), Some(map)) = (threshold, &map) {
+        let points = map.at_least(threshold);
+        println!(
+            "{} points crossed by at least {} lines:",
+            points.len(),
+            threshold
+        );
+        for (p, count) in points {
+            println!("    {} ({})", p, count);
+        }
     }
-}
-
-impl FromStr for Vec2 {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s.split(',').collect::<Vec<_>>();
-        Ok(Vec2 {
-            x: parts[0].parse()?,
-            y: parts[1].parse()?,
-        })
+    if let (true, Some(crossings)) = (show_crossings, &crossings) {
+        for c in crossings.iter() {
+            println!("{} and {} share {}", lines[c.a], lines[c.b], c.overlap);
+        }
+        println!(
+            "{} dangerous points from {} crossings",
+            overlap::dangerous(crossings),
+            crossings.len()
+        );
     }
+    Ok(())
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Line {
-    start: Vec2,
-    end: Vec2,
-}
-
-impl Line {
-    fn is_hori_or_vert(&self) -> bool {
-        self.start.x == self.end.x || self.start.y == self.end.y
-    }
+// Paints every line onto a map, printing both parts along the way.
+fn paint(lines: &[Line]) -> Result<Map> {
+    // sized to the bounding box of the lines, or sparse if that is huge
+    let mut map = Map::for_lines(lines);
 
-    fn get_direction(&self) -> (isize, isize) {
-        let x = self.end.x - self.start.x;
-        let y = self.end.y - self.start.y;
-        (x / x.abs(), y / y.abs())
+    for l in lines.iter().filter(|l| l.is_hori_or_vert()) {
+        map.add_line(l)?;
     }
-}
+    println!("part 1: {:#?}", map.dangerous());
 
-impl FromStr for Line {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s.split(" -> ").collect::<Vec<_>>();
-        Ok(Line {
-            start: parts[0].parse()?,
-            end: parts[1].parse()?,
-        })
+    // then every other line, at whatever slope
+    for l in lines.iter().filter(|l| !l.is_hori_or_vert()) {
+        map.add_line(l)?;
     }
+    println!("part 2: {:#?}", map.dangerous());
+    Ok(map)
 }
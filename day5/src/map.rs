@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use ndarray::Array2;
+
+use crate::{Line, Vec2};
+
+// Bounding boxes with more cells than this are counted in a hash map instead.
+const MAX_DENSE_CELLS: usize = 1 << 22;
+
+// The smallest box holding every point, inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Bounds {
+    pub fn of_lines(lines: &[Line]) -> Option<Bounds> {
+        Bounds::of_points(lines.iter().flat_map(|l| [l.start, l.end]))
+    }
+
+    pub fn of_points(points: impl IntoIterator<Item = Vec2>) -> Option<Bounds> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |b, p| Bounds {
+                min: Vec2 {
+                    x: b.min.x.min(p.x),
+                    y: b.min.y.min(p.y),
+                },
+                max: Vec2 {
+                    x: b.max.x.max(p.x),
+                    y: b.max.y.max(p.y),
+                },
+            },
+        ))
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x) as usize + 1
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y) as usize + 1
+    }
+
+    pub fn contains(&self, p: Vec2) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+
+    // where a point inside the bounds sits in a grid starting at the min corner
+    fn index(&self, p: Vec2) -> [usize; 2] {
+        [(p.x - self.min.x) as usize, (p.y - self.min.y) as usize]
+    }
+}
+
+enum Cells {
+    // counts for every cell of the bounds, indexed from its min corner
+    Dense(Array2<usize>),
+    // counts for only the cells a line crosses
+    Sparse(HashMap<Vec2, usize>),
+}
+
+pub struct Map {
+    bounds: Bounds,
+    cells: Cells,
+}
+
+impl Map {
+    // A dense grid when the lines' bounding box is small enough, sparse otherwise.
+    pub fn for_lines(lines: &[Line]) -> Map {
+        let bounds = Bounds::of_lines(lines).unwrap_or(Bounds {
+            min: Vec2 { x: 0, y: 0 },
+            max: Vec2 { x: 0, y: 0 },
+        });
+        match bounds.width().checked_mul(bounds.height()) {
+            Some(cells) if cells <= MAX_DENSE_CELLS => Map::dense(bounds),
+            _ => Map::sparse(bounds),
+        }
+    }
+
+    pub fn dense(bounds: Bounds) -> Map {
+        Map {
+            bounds,
+            cells: Cells::Dense(Array2::zeros((bounds.width(), bounds.height()))),
+        }
+    }
+
+    pub fn sparse(bounds: Bounds) -> Map {
+        Map {
+            bounds,
+            cells: Cells::Sparse(HashMap::new()),
+        }
+    }
+
+    pub fn add_line(&mut self, line: &Line) -> Result<()> {
+        if !self.bounds.contains(line.start) || !self.bounds.contains(line.end) {
+            bail!(
+                "line {} goes outside the map, which spans {} to {}",
+                line,
+                self.bounds.min,
+                self.bounds.max
+            );
+        }
+        for p in line.points() {
+            match &mut self.cells {
+                Cells::Dense(counts) => counts[self.bounds.index(p)] += 1,
+                Cells::Sparse(counts) => *counts.entry(p).or_default() += 1,
+            }
+        }
+        Ok(())
+    }
+
+    // how many lines cover the point
+    pub fn get(&self, p: Vec2) -> usize {
+        if !self.bounds.contains(p) {
+            return 0;
+        }
+        match &self.cells {
+            Cells::Dense(counts) => counts[self.bounds.index(p)],
+            Cells::Sparse(counts) => counts.get(&p).copied().unwrap_or(0),
+        }
+    }
+
+    // every point covered by at least one line, with how many cover it
+    pub fn covered(&self) -> Box<dyn Iterator<Item = (Vec2, usize)> + '_> {
+        match &self.cells {
+            Cells::Dense(counts) => Box::new(counts.indexed_iter().filter(|(_, &c)| c > 0).map(
+                move |((x, y), &c)| {
+                    let p = Vec2 {
+                        x: self.bounds.min.x + x as isize,
+                        y: self.bounds.min.y + y as isize,
+                    };
+                    (p, c)
+                },
+            )),
+            Cells::Sparse(counts) => Box::new(counts.iter().map(|(&p, &c)| (p, c))),
+        }
+    }
+
+    // the points covered by at least two lines
+    pub fn dangerous(&self) -> usize {
+        match &self.cells {
+            Cells::Dense(counts) => counts.iter().filter(|&&c| c >= 2).count(),
+            Cells::Sparse(counts) => counts.values().filter(|&&c| c >= 2).count(),
+        }
+    }
+
+    pub fn render(&self) {
+        for y in self.bounds.min.y..=self.bounds.max.y {
+            for x in self.bounds.min.x..=self.bounds.max.x {
+                match self.get(Vec2 { x, y }) {
+                    0 => print!("."),
+                    val => print!("{}", val),
+                }
+            }
+            println!();
+        }
+    }
+}
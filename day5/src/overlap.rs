@@ -0,0 +1,218 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Line, Vec2};
+
+// The lattice points two lines have in common.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    Point(Vec2),
+    // collinear lines sharing every point from start to end
+    Segment(Line),
+}
+
+impl fmt::Display for Overlap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Overlap::Point(p) => write!(f, "{}", p),
+            Overlap::Segment(l) => write!(f, "{}", l),
+        }
+    }
+}
+
+// Two of the input lines, by index, and what they share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crossing {
+    pub a: usize,
+    pub b: usize,
+    pub overlap: Overlap,
+}
+
+/*
+    Lines are the lattice points they pass through, so lines crossing between
+    lattice points, like 0,0 -> 1,1 and 0,1 -> 1,0, share nothing. Everything
+    is worked out in i128 as the products of spans can overflow isize.
+*/
+pub fn overlap(a: &Line, b: &Line) -> Option<Overlap> {
+    let da = span(a);
+    let db = span(b);
+    if da == (0, 0) || db == (0, 0) {
+        let (point, line) = if da == (0, 0) { (a, b) } else { (b, a) };
+        return line
+            .contains(point.start)
+            .then_some(Overlap::Point(point.start));
+    }
+
+    let to_b = (
+        b.start.x as i128 - a.start.x as i128,
+        b.start.y as i128 - a.start.y as i128,
+    );
+    let denom = cross(da, db);
+    if denom == 0 {
+        if cross(to_b, da) != 0 {
+            // parallel, on different lines
+            return None;
+        }
+        return collinear(a, b);
+    }
+
+    // a.start + da * t == b.start + db * s, with t and s in [0, 1]
+    let (mut t, mut s, mut denom) = (cross(to_b, db), cross(to_b, da), denom);
+    if denom < 0 {
+        (t, s, denom) = (-t, -s, -denom);
+    }
+    if !(0..=denom).contains(&t) || !(0..=denom).contains(&s) {
+        return None;
+    }
+    let (x, y) = (da.0 * t, da.1 * t);
+    if x % denom != 0 || y % denom != 0 {
+        // crosses between lattice points
+        return None;
+    }
+    Some(Overlap::Point(Vec2 {
+        x: (a.start.x as i128 + x / denom) as isize,
+        y: (a.start.y as i128 + y / denom) as isize,
+    }))
+}
+
+// the shared part of two lines on the same infinite line
+fn collinear(a: &Line, b: &Line) -> Option<Overlap> {
+    let key = LineKey::of(a);
+    let (a0, a1) = key.interval(a);
+    let (b0, b1) = key.interval(b);
+    let (lo, hi) = (a0.max(b0), a1.min(b1));
+    if lo > hi {
+        return None;
+    }
+    let (start, end) = (key.point(a.start, lo), key.point(a.start, hi));
+    Some(match lo == hi {
+        true => Overlap::Point(start),
+        false => Overlap::Segment(Line { start, end }),
+    })
+}
+
+// every pair of lines that shares at least one point
+pub fn crossings(lines: &[Line]) -> Vec<Crossing> {
+    let mut crossings = vec![];
+    for (a, first) in lines.iter().enumerate() {
+        for (b, second) in lines.iter().enumerate().skip(a + 1) {
+            if let Some(overlap) = overlap(first, second) {
+                crossings.push(Crossing { a, b, overlap });
+            }
+        }
+    }
+    crossings
+}
+
+/*
+    The points covered by at least two lines, counted from the crossings
+    alone. Shared segments are merged along the line they lie on, and shared
+    points only count when no merged segment already holds them, so nothing
+    is ever painted point by point.
+*/
+pub fn dangerous(crossings: &[Crossing]) -> usize {
+    let mut segments: HashMap<LineKey, Vec<(i128, i128)>> = HashMap::new();
+    let mut points = vec![];
+    for crossing in crossings {
+        match crossing.overlap {
+            Overlap::Point(p) => points.push(p),
+            Overlap::Segment(l) => {
+                let key = LineKey::of(&l);
+                segments.entry(key).or_default().push(key.interval(&l));
+            }
+        }
+    }
+
+    let mut count = 0;
+    for (key, intervals) in segments.iter_mut() {
+        intervals.sort_unstable();
+        let mut merged: Vec<(i128, i128)> = vec![];
+        for &(lo, hi) in intervals.iter() {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+        count += merged
+            .iter()
+            .map(|(lo, hi)| ((hi - lo) / key.step + 1) as usize)
+            .sum::<usize>();
+        *intervals = merged;
+    }
+
+    points.sort_unstable_by_key(|p| (p.x, p.y));
+    points.dedup();
+    count
+        + points
+            .iter()
+            .filter(|&&p| {
+                !segments.iter().any(|(key, intervals)| {
+                    key.holds(p)
+                        && intervals
+                            .iter()
+                            .any(|&(lo, hi)| (lo..=hi).contains(&key.at(p)))
+                })
+            })
+            .count()
+}
+
+fn span(l: &Line) -> (i128, i128) {
+    (
+        l.end.x as i128 - l.start.x as i128,
+        l.end.y as i128 - l.start.y as i128,
+    )
+}
+
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/*
+    An infinite line through lattice points, as its smallest step pointing
+    right (or down, when vertical) and the cross product every point on it
+    has with that step. Points along it are placed by their dot product with
+    the step, which grows by `step` from one lattice point to the next.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LineKey {
+    dir: (i128, i128),
+    offset: i128,
+    step: i128,
+}
+
+impl LineKey {
+    fn of(l: &Line) -> LineKey {
+        let (x, y) = l.direction();
+        let dir = match (x, y) {
+            (x, y) if x < 0 || (x == 0 && y < 0) => (-x as i128, -y as i128),
+            (x, y) => (x as i128, y as i128),
+        };
+        LineKey {
+            dir,
+            offset: cross((l.start.x as i128, l.start.y as i128), dir),
+            step: dir.0 * dir.0 + dir.1 * dir.1,
+        }
+    }
+
+    fn holds(&self, p: Vec2) -> bool {
+        cross((p.x as i128, p.y as i128), self.dir) == self.offset
+    }
+
+    fn at(&self, p: Vec2) -> i128 {
+        p.x as i128 * self.dir.0 + p.y as i128 * self.dir.1
+    }
+
+    // where a line on this one starts and ends, lowest first
+    fn interval(&self, l: &Line) -> (i128, i128) {
+        let (s, e) = (self.at(l.start), self.at(l.end));
+        (s.min(e), s.max(e))
+    }
+
+    // the point at `at` along the line through `origin`
+    fn point(&self, origin: Vec2, at: i128) -> Vec2 {
+        let steps = (at - self.at(origin)) / self.step;
+        Vec2 {
+            x: (origin.x as i128 + steps * self.dir.0) as isize,
+            y: (origin.y as i128 + steps * self.dir.1) as isize,
+        }
+    }
+}
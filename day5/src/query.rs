@@ -0,0 +1,28 @@
+use crate::{
+    map::{Bounds, Map},
+    Line, Vec2,
+};
+
+// the lines passing through the point, in the order they were given
+pub fn lines_through(lines: &[Line], p: Vec2) -> Vec<&Line> {
+    lines.iter().filter(|l| l.contains(p)).collect()
+}
+
+impl Map {
+    // the points inside the rectangle covered by at least two lines
+    pub fn dangerous_in(&self, rect: Bounds) -> usize {
+        self.covered()
+            .filter(|&(p, c)| c >= 2 && rect.contains(p))
+            .count()
+    }
+
+    // the points covered by at least `threshold` lines, row by row
+    pub fn at_least(&self, threshold: usize) -> Vec<(Vec2, usize)> {
+        let mut points = self
+            .covered()
+            .filter(|&(_, c)| c >= threshold)
+            .collect::<Vec<_>>();
+        points.sort_unstable_by_key(|(p, _)| (p.y, p.x));
+        points
+    }
+}
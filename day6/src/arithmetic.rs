@@ -0,0 +1,62 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+use num_bigint::BigUint;
+
+// The numbers the population is counted in.
+pub trait Arithmetic {
+    type Value: Clone + fmt::Display;
+
+    fn number(&self, n: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+// Arbitrary precision, for the exact population however large it grows.
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn number(&self, n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+// The population modulo some number, for days where even writing it out is too much.
+pub struct Modular {
+    modulus: u64,
+}
+
+impl Modular {
+    pub fn new(modulus: u64) -> Result<Modular> {
+        if modulus == 0 {
+            bail!("the modulus must be at least 1");
+        }
+        Ok(Modular { modulus })
+    }
+}
+
+impl Arithmetic for Modular {
+    type Value = u64;
+
+    fn number(&self, n: u64) -> u64 {
+        n % self.modulus
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.modulus as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 * *b as u128) % self.modulus as u128) as u64
+    }
+}
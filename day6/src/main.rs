@@ -1,17 +1,290 @@
-fn main() {
-    let mut fish: [u64; 9] = include_str!("input")
-        .split(',')
-        .map(|i| i.parse::<usize>().unwrap())
-        .fold([0; 9], |mut acc, val| {
-            acc[val] += 1;
-            acc
-        });
-
-    for d in 0..=256 {
-        if d == 80 || d == 256 {
-            println!("Day {}, Fish: {:?}", d, fish.iter().sum::<u64>());
+mod arithmetic;
+mod matrix;
+mod population;
+mod series;
+mod stochastic;
+
+use std::{fs::File, io::BufWriter, ops::RangeInclusive};
+
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigUint;
+
+use arithmetic::{Arithmetic, Exact, Modular};
+use population::{census, population, Timers};
+
+/*
+    Usage: day6 [--days <d,...>] [--mod <m>] [--reset <timer>] [--newborn <timer>]
+                [--range <from-to>] [--csv <path>] [--above <n>] [--peak <timer>]
+                [--spawn <chance> [--lifespan <days>] [--trials <n>] [--seed <n>]]
+
+    The number of fish after each of the days, 80 and 256 unless told
+    otherwise. Days can be as large as a u64 holds; `--mod` gives the count
+    modulo m instead of exactly. `--reset` and `--newborn` change the timers
+    from 6 and 8.
+
+    `--csv` writes the total and the fish at each timer value for every day
+    in `--range`, 0-256 by default. `--above` finds the first day with more
+    than n fish, and `--peak` the day in the range with the most fish at that
+    timer value; both are worked out exactly, whatever `--mod` says.
+
+    `--spawn` switches to fish that have a child with that chance whenever
+    their timer runs out, and that die after `--lifespan` days if given. The
+    expected population is worked out for every day in `--range` and compared
+    against `--trials` seeded simulations, 1000 by default.
+*/
+fn main() -> Result<()> {
+    let mut days = vec![80, 256];
+    let mut modulus = None;
+    let mut timers = Timers::default();
+    let mut range = 0..=256;
+    let mut csv = None;
+    let mut above = None;
+    let mut peak = None;
+    let mut spawn = None;
+    let mut lifespan = None;
+    let mut trials = 1000;
+    let mut seed = 0;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--days" => {
+                days = value()?
+                    .split(',')
+                    .map(|d| d.parse())
+                    .collect::<Result<_, _>>()?
+            }
+            "--mod" => modulus = Some(value()?.parse()?),
+            "--reset" => timers.reset = value()?.parse()?,
+            "--newborn" => timers.newborn = value()?.parse()?,
+            "--range" => range = series::parse_days(&value()?)?,
+            "--csv" => csv = Some(value()?),
+            "--above" => above = Some(value()?.parse::<BigUint>()?),
+            "--peak" => peak = Some(value()?.parse()?),
+            "--spawn" => spawn = Some(value()?.parse()?),
+            "--lifespan" => lifespan = Some(value()?.parse()?),
+            "--trials" => trials = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            _ => bail!("unknown argument `{}`", arg),
+        }
+    }
+    let timers = Timers::new(timers.reset, timers.newborn)?;
+
+    let fish = census(include_str!("input"), timers)?;
+    match modulus {
+        Some(m) => report(&Modular::new(m)?, timers, &fish, &days, csv, &range)?,
+        None => report(&Exact, timers, &fish, &days, csv, &range)?,
+    }
+
+    if let Some(n) = above {
+        match series::first_day_above(timers, &fish, &n) {
+            Some(day) => println!("More than {} fish from day {}", n, day),
+            None => println!("There are never more than {} fish", n),
+        }
+    }
+    if let Some(timer) = peak {
+        let (day, count) = series::peak(timers, &fish, timer, range.clone())?;
+        println!("Most fish at timer {} on day {}: {}", timer, day, count);
+    }
+
+    if let Some(spawn) = spawn {
+        let model = stochastic::Model::new(timers, spawn, lifespan)?;
+        println!("  day    expected        mean     std dev          5%      median         95%");
+        for stats in model.simulate(&fish, range, trials, seed)? {
+            println!(
+                "{:>5} {:>11.1} {:>11.1} {:>11.1} {:>11} {:>11} {:>11}",
+                stats.day,
+                stats.expected,
+                stats.mean,
+                stats.variance.sqrt(),
+                stats.p5,
+                stats.median,
+                stats.p95
+            );
+        }
+    }
+    Ok(())
+}
+
+fn report<A: Arithmetic>(
+    a: &A,
+    timers: Timers,
+    fish: &[u64],
+    days: &[u64],
+    csv: Option<String>,
+    range: &RangeInclusive<u64>,
+) -> Result<()> {
+    for &d in days {
+        println!("Day {}, Fish: {}", d, population(a, timers, fish, d));
+    }
+    if let Some(path) = csv {
+        let mut out = BufWriter::new(File::create(path)?);
+        series::write_csv(a, timers, fish, range.clone(), &mut out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the original model, one day at a time
+    fn step_days(timers: Timers, fish: &[u64], days: u64) -> u128 {
+        let mut fish = fish.iter().map(|&f| f as u128).collect::<Vec<_>>();
+        for _ in 0..days {
+            fish.rotate_left(1);
+            fish[timers.reset] += fish[timers.newborn];
+        }
+        fish.iter().sum()
+    }
+
+    #[test]
+    fn matrix_matches_stepping() {
+        for timers in [
+            Timers::default(),
+            Timers::new(2, 3).unwrap(),
+            Timers::new(4, 4).unwrap(),
+        ] {
+            let fish = census("3,3,2,1,0,1", timers).unwrap();
+            for days in [0, 1, 2, 18, 80, 256, 300] {
+                let stepped = step_days(timers, &fish, days);
+                assert_eq!(
+                    stepped.to_string(),
+                    population(&Exact, timers, &fish, days).to_string()
+                );
+                let modular = Modular::new(1_000_000_007).unwrap();
+                assert_eq!(
+                    (stepped % 1_000_000_007) as u64,
+                    population(&modular, timers, &fish, days)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn series_and_queries() {
+        let timers = Timers::new(2, 3).unwrap();
+        let fish = census("3,3,2,1,0,1", timers).unwrap();
+        for (day, by_timer) in series::series(&Exact, timers, &fish, 5..=40) {
+            assert_eq!(by_timer, population::by_timer(&Exact, timers, &fish, day));
+        }
+
+        let mut csv = vec![];
+        series::write_csv(
+            &Exact,
+            Timers::default(),
+            &[0, 1, 0, 0, 0, 0, 0, 0, 0],
+            0..=2,
+            &mut csv,
+        )
+        .unwrap();
+        assert_eq!(
+            "day,total,timer0,timer1,timer2,timer3,timer4,timer5,timer6,timer7,timer8\n\
+             0,1,0,1,0,0,0,0,0,0,0\n\
+             1,1,1,0,0,0,0,0,0,0,0\n\
+             2,2,0,0,0,0,0,0,1,0,1\n",
+            String::from_utf8(csv).unwrap()
+        );
+
+        let fish = census("3,4,3,1,2", Timers::default()).unwrap();
+        let above = |n: u64| series::first_day_above(Timers::default(), &fish, &BigUint::from(n));
+        assert_eq!(Some(80), above(5933));
+        assert_eq!(Some(81), above(5934));
+        assert_eq!(Some(0), above(4));
+        assert_eq!(
+            None,
+            series::first_day_above(Timers::default(), &[0; 9], &BigUint::from(1u32))
+        );
+
+        // on days 0 to 3, the fish at timer 1 go 1, 1, 2, 1
+        let (day, count) = series::peak(Timers::default(), &fish, 1, 0..=3).unwrap();
+        assert_eq!((2, BigUint::from(2u32)), (day, count));
+        assert!(series::peak(Timers::default(), &fish, 9, 0..=3).is_err());
+        assert!(series::parse_days("5-4").is_err());
+        assert_eq!(0..=256, series::parse_days("0-256").unwrap());
+    }
+
+    #[test]
+    fn stochastic_model() {
+        let timers = Timers::default();
+        let fish = census("3,4,3,1,2", timers).unwrap();
+
+        // always spawning and never dying is the original model
+        let certain = stochastic::Model::new(timers, 1.0, None).unwrap();
+        assert_eq!(5934.0, certain.expected(&fish, 80)[80]);
+        let stats = certain.simulate(&fish, 80..=80, 3, 1).unwrap();
+        assert_eq!(
+            (5934, 5934, 0.0),
+            (stats[0].p5, stats[0].p95, stats[0].variance)
+        );
+        // the original model outgrows a u64 a little past day 400
+        let err = certain.simulate(&fish, 520..=521, 1, 1).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+
+        // fish that live 5 days all die before they can spawn a second time
+        let short = stochastic::Model::new(timers, 1.0, Some(5)).unwrap();
+        let expected = short.expected(&fish, 12);
+        assert_eq!(vec![5.0, 5.0, 6.0, 7.0, 9.0, 5.0, 5.0, 4.0], expected[..8]);
+        assert_eq!(0.0, expected[12]);
+        // a lifespan longer than the days asked for costs nothing to track
+        let ageless = stochastic::Model::new(timers, 1.0, Some(1_000_000_000)).unwrap();
+        assert_eq!(5934.0, ageless.expected(&fish, 80)[80]);
+        assert_eq!(
+            5934,
+            ageless.simulate(&fish, 80..=80, 1, 1).unwrap()[0].median
+        );
+
+        let chancy = stochastic::Model::new(timers, 0.5, Some(20)).unwrap();
+        let stats = chancy.simulate(&fish, 0..=40, 2000, 7).unwrap();
+        let single = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            stats,
+            single.install(|| chancy.simulate(&fish, 0..=40, 2000, 7).unwrap())
+        );
+        for s in stats.iter() {
+            assert!(
+                (s.mean - s.expected).abs() <= 0.05 * s.expected + 0.5,
+                "{:?}",
+                s
+            );
+            assert!(s.p5 <= s.median && s.median <= s.p95);
         }
-        fish.rotate_left(1);
-        fish[6] += fish[8];
+        assert_eq!(5.0, stats[0].expected);
+        assert!(stats[40].variance > 0.0);
+
+        assert!(stochastic::Model::new(timers, 1.5, None).is_err());
+        assert!(stochastic::Model::new(timers, 0.5, Some(0)).is_err());
+    }
+
+    #[test]
+    fn example_and_huge_days() {
+        let fish = census("3,4,3,1,2", Timers::default()).unwrap();
+        let exact = |days| population(&Exact, Timers::default(), &fish, days).to_string();
+        assert_eq!("5934", exact(80));
+        assert_eq!("26984457539", exact(256));
+        // past what a u64 holds
+        let exact = population(&Exact, Timers::default(), &fish, 1000);
+        assert!(exact.bits() > 64);
+        let modular = Modular::new(1 << 61).unwrap();
+        assert_eq!(
+            (exact % (1u64 << 61)).to_string(),
+            population(&modular, Timers::default(), &fish, 1000).to_string()
+        );
+        population(
+            &modular,
+            Timers::default(),
+            &fish,
+            1_000_000_000_000_000_000,
+        );
+
+        assert!(Timers::new(7, 6).is_err());
+        assert!(census("3,9", Timers::default()).is_err());
+        assert!(Modular::new(0).is_err());
     }
 }
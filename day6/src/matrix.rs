@@ -0,0 +1,59 @@
+use crate::arithmetic::Arithmetic;
+
+// A square matrix, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn from_fn<A>(a: &A, size: usize, f: impl Fn(usize, usize) -> u64) -> Matrix<T>
+    where
+        A: Arithmetic<Value = T>,
+    {
+        Matrix {
+            size,
+            cells: (0..size * size)
+                .map(|i| a.number(f(i / size, i % size)))
+                .collect(),
+        }
+    }
+
+    pub fn identity<A: Arithmetic<Value = T>>(a: &A, size: usize) -> Matrix<T> {
+        Matrix::from_fn(a, size, |r, c| (r == c) as u64)
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> &T {
+        &self.cells[row * self.size + column]
+    }
+
+    pub fn mul<A: Arithmetic<Value = T>>(&self, a: &A, other: &Matrix<T>) -> Matrix<T> {
+        let n = self.size;
+        let cells = (0..n * n)
+            .map(|i| {
+                let (r, c) = (i / n, i % n);
+                (1..n).fold(a.mul(self.get(r, 0), other.get(0, c)), |sum, k| {
+                    a.add(&sum, &a.mul(self.get(r, k), other.get(k, c)))
+                })
+            })
+            .collect();
+        Matrix { size: n, cells }
+    }
+
+    // by repeated squaring, so in log2(power) multiplications
+    pub fn pow<A: Arithmetic<Value = T>>(&self, a: &A, mut power: u64) -> Matrix<T> {
+        let mut result = Matrix::identity(a, self.size);
+        let mut square = self.clone();
+        while power > 0 {
+            if power & 1 == 1 {
+                result = result.mul(a, &square);
+            }
+            power >>= 1;
+            if power > 0 {
+                square = square.mul(a, &square);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{bail, Result};
+
+use crate::{arithmetic::Arithmetic, matrix::Matrix};
+
+// What a fish's timer is set to after it spawns, and what a newborn's starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timers {
+    pub reset: usize,
+    pub newborn: usize,
+}
+
+impl Default for Timers {
+    fn default() -> Timers {
+        Timers {
+            reset: 6,
+            newborn: 8,
+        }
+    }
+}
+
+impl Timers {
+    pub fn new(reset: usize, newborn: usize) -> Result<Timers> {
+        if reset > newborn {
+            bail!(
+                "the reset timer ({}) can't be above the newborn timer ({})",
+                reset,
+                newborn
+            );
+        }
+        Ok(Timers { reset, newborn })
+    }
+
+    // how many timer values a fish can have
+    pub fn states(&self) -> usize {
+        self.newborn + 1
+    }
+
+    /*
+        One day as a matrix taking the fish at each timer value to the fish
+        at each value the next day: every timer counts down, and those at 0
+        go back to `reset` and each add a fish at `newborn`.
+    */
+    pub fn transition<A: Arithmetic>(&self, a: &A) -> Matrix<A::Value> {
+        Matrix::from_fn(a, self.states(), |next, now| match now {
+            0 => (next == self.reset) as u64 + (next == self.newborn) as u64,
+            _ => (next + 1 == now) as u64,
+        })
+    }
+}
+
+// how many fish start at each timer value
+pub fn census(input: &str, timers: Timers) -> Result<Vec<u64>> {
+    let mut fish = vec![0; timers.states()];
+    for timer in input.trim().split(',') {
+        let timer = timer.trim().parse::<usize>()?;
+        if timer >= fish.len() {
+            bail!(
+                "a fish's timer is {}, above the newborn timer {}",
+                timer,
+                timers.newborn
+            );
+        }
+        fish[timer] += 1;
+    }
+    Ok(fish)
+}
+
+// how many fish are at each timer value after `days` days
+pub fn by_timer<A: Arithmetic>(a: &A, timers: Timers, fish: &[u64], days: u64) -> Vec<A::Value> {
+    let days = timers.transition(a).pow(a, days);
+    (0..fish.len())
+        .map(|row| {
+            (0..fish.len()).fold(a.number(0), |sum, column| {
+                a.add(&sum, &a.mul(days.get(row, column), &a.number(fish[column])))
+            })
+        })
+        .collect()
+}
+
+// the number of fish after `days` days
+pub fn population<A: Arithmetic>(a: &A, timers: Timers, fish: &[u64], days: u64) -> A::Value {
+    total(a, &by_timer(a, timers, fish, days))
+}
+
+pub fn total<A: Arithmetic>(a: &A, fish: &[A::Value]) -> A::Value {
+    fish.iter().fold(a.number(0), |sum, f| a.add(&sum, f))
+}
+
+// the fish at each timer value a day later
+pub fn advance<A: Arithmetic>(a: &A, timers: Timers, fish: &[A::Value]) -> Vec<A::Value> {
+    let mut next = (0..fish.len())
+        .map(|timer| match fish.get(timer + 1) {
+            Some(f) => f.clone(),
+            None => a.number(0),
+        })
+        .collect::<Vec<_>>();
+    next[timers.reset] = a.add(&next[timers.reset], &fish[0]);
+    next[timers.newborn] = a.add(&next[timers.newborn], &fish[0]);
+    next
+}
@@ -0,0 +1,97 @@
+use std::{io::Write, ops::RangeInclusive};
+
+use anyhow::{bail, Result};
+use num_bigint::BigUint;
+
+use crate::{
+    arithmetic::{Arithmetic, Exact},
+    population::{advance, by_timer, total, Timers},
+};
+
+// The fish at each timer value on every day of the range, starting with a
+// jump to its first day and then going a day at a time.
+pub fn series<'a, A: Arithmetic>(
+    a: &'a A,
+    timers: Timers,
+    fish: &[u64],
+    days: RangeInclusive<u64>,
+) -> impl Iterator<Item = (u64, Vec<A::Value>)> + 'a {
+    let start = by_timer(a, timers, fish, *days.start());
+    days.scan(start, move |today, day| {
+        let tomorrow = advance(a, timers, today);
+        Some((day, std::mem::replace(today, tomorrow)))
+    })
+}
+
+// one row per day, with the total and then the fish at each timer value
+pub fn write_csv<A: Arithmetic>(
+    a: &A,
+    timers: Timers,
+    fish: &[u64],
+    days: RangeInclusive<u64>,
+    out: &mut impl Write,
+) -> Result<()> {
+    write!(out, "day,total")?;
+    for timer in 0..timers.states() {
+        write!(out, ",timer{}", timer)?;
+    }
+    writeln!(out)?;
+    for (day, by_timer) in series(a, timers, fish, days) {
+        write!(out, "{},{}", day, total(a, &by_timer))?;
+        for f in by_timer.iter() {
+            write!(out, ",{}", f)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/*
+    The first day there are more than `n` fish. Fish never die and each has
+    a child within `newborn + 1` days, so the population at least doubles
+    that often and this is only a short walk unless there are no fish at all.
+*/
+pub fn first_day_above(timers: Timers, fish: &[u64], n: &BigUint) -> Option<u64> {
+    if fish.iter().all(|&f| f == 0) {
+        return None;
+    }
+    let mut today = fish.iter().map(|&f| BigUint::from(f)).collect::<Vec<_>>();
+    let mut day = 0;
+    while total(&Exact, &today) <= *n {
+        today = advance(&Exact, timers, &today);
+        day += 1;
+    }
+    Some(day)
+}
+
+// the day in the range with the most fish at the timer value, the earliest if tied
+pub fn peak(
+    timers: Timers,
+    fish: &[u64],
+    timer: usize,
+    days: RangeInclusive<u64>,
+) -> Result<(u64, BigUint)> {
+    if timer >= timers.states() {
+        bail!("timers only go up to {}", timers.newborn);
+    }
+    let mut best: Option<(u64, BigUint)> = None;
+    for (day, mut by_timer) in series(&Exact, timers, fish, days) {
+        let count = by_timer.swap_remove(timer);
+        if best.as_ref().is_none_or(|(_, most)| count > *most) {
+            best = Some((day, count));
+        }
+    }
+    best.ok_or_else(|| anyhow::anyhow!("the range of days is empty"))
+}
+
+// a range of days like `0-256`
+pub fn parse_days(s: &str) -> Result<RangeInclusive<u64>> {
+    let Some((from, to)) = s.split_once('-') else {
+        bail!("expected a range of days like `0-256`, found `{}`", s);
+    };
+    let (from, to) = (from.parse()?, to.parse()?);
+    if from > to {
+        bail!("the range of days {} runs backwards", s);
+    }
+    Ok(from..=to)
+}
@@ -0,0 +1,223 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, bail, Result};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Binomial, Distribution};
+use rayon::prelude::*;
+
+use crate::population::Timers;
+
+/*
+    Fish that only have a child with probability `spawn` each time their
+    timer runs out, and that live `lifespan` days if they have one. A fish
+    whose timer runs out on its last day may still have a child. The fish at
+    the start are all taken to be newly born.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Model {
+    pub timers: Timers,
+    pub spawn: f64,
+    pub lifespan: Option<u64>,
+}
+
+// A number of fish, which can say when adding to it overflows.
+trait Count: Copy + Default + PartialEq {
+    fn plus(self, other: Self) -> Option<Self>;
+}
+
+impl Count for u64 {
+    fn plus(self, other: u64) -> Option<u64> {
+        self.checked_add(other)
+    }
+}
+
+impl Count for f64 {
+    fn plus(self, other: f64) -> Option<f64> {
+        Some(self + other)
+    }
+}
+
+// How the population is spread on one day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayStats {
+    pub day: u64,
+    // worked out exactly, the rest are over the trials
+    pub expected: f64,
+    pub mean: f64,
+    pub variance: f64,
+    pub p5: u64,
+    pub median: u64,
+    pub p95: u64,
+}
+
+impl Model {
+    pub fn new(timers: Timers, spawn: f64, lifespan: Option<u64>) -> Result<Model> {
+        if !(0.0..=1.0).contains(&spawn) {
+            bail!(
+                "the chance of spawning must be between 0 and 1, not {}",
+                spawn
+            );
+        }
+        if lifespan == Some(0) {
+            bail!("fish have to live for at least a day");
+        }
+        Ok(Model {
+            timers,
+            spawn,
+            lifespan,
+        })
+    }
+
+    // The same model for the first `days` days, in which fish with a longer
+    // lifespan never die, so their ages needn't be kept.
+    fn up_to(&self, days: u64) -> Model {
+        Model {
+            lifespan: self.lifespan.filter(|&lifespan| lifespan <= days),
+            ..*self
+        }
+    }
+
+    // the ages that are told apart; without a lifespan age doesn't matter
+    fn ages(&self) -> usize {
+        self.lifespan.unwrap_or(1) as usize
+    }
+
+    // the fish bucketed by timer and then age, from how many start at each timer
+    fn start<T: Copy + Default>(&self, fish: &[T]) -> Vec<T> {
+        let mut buckets = vec![T::default(); self.timers.states() * self.ages()];
+        for (timer, &f) in fish.iter().enumerate() {
+            buckets[timer * self.ages()] = f;
+        }
+        buckets
+    }
+
+    // a day later, with `births` deciding how many of n spawning fish have a
+    // child, or `None` if a count overflows
+    fn step<T: Count>(&self, now: &[T], mut births: impl FnMut(T) -> T) -> Option<Vec<T>> {
+        let ages = self.ages();
+        let mut next = vec![T::default(); now.len()];
+        for (bucket, &n) in now.iter().enumerate() {
+            if n == T::default() {
+                continue;
+            }
+            let (timer, age) = (bucket / ages, bucket % ages);
+            if timer == 0 {
+                let newborn = self.timers.newborn * ages;
+                next[newborn] = next[newborn].plus(births(n))?;
+            }
+            let older = match self.lifespan {
+                Some(lifespan) if age as u64 + 1 >= lifespan => continue,
+                Some(_) => age + 1,
+                None => 0,
+            };
+            let timer = match timer {
+                0 => self.timers.reset,
+                _ => timer - 1,
+            };
+            next[timer * ages + older] = next[timer * ages + older].plus(n)?;
+        }
+        Some(next)
+    }
+
+    // the expected number of fish on each day up to `days`, from day 0
+    pub fn expected(&self, fish: &[u64], days: u64) -> Vec<f64> {
+        let model = self.up_to(days);
+        let fish = fish.iter().map(|&f| f as f64).collect::<Vec<_>>();
+        let mut today = model.start(&fish);
+        let mut totals = vec![today.iter().sum()];
+        for _ in 0..days {
+            today = model
+                .step(&today, |n| n * model.spawn)
+                .expect("floats don't overflow");
+            totals.push(today.iter().sum());
+        }
+        totals
+    }
+
+    /*
+        Runs `trials` populations up to the last day of the range, and sums up
+        every day in it. Counts are kept in u64s, and a population that
+        outgrows them is an error rather than wrapping around.
+
+        Trial `i` takes its births from stream `i` of a generator seeded with
+        `seed`, so a seed always replays the same populations, and each day's
+        statistics come from its totals sorted by size.
+    */
+    pub fn simulate(
+        &self,
+        fish: &[u64],
+        days: RangeInclusive<u64>,
+        trials: usize,
+        seed: u64,
+    ) -> Result<Vec<DayStats>> {
+        if trials == 0 {
+            bail!("there has to be at least one trial");
+        }
+        let last = *days.end();
+        let model = self.up_to(last);
+        let runs = (0..trials)
+            .into_par_iter()
+            .map(|trial| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_stream(trial as u64);
+                let mut today = model.start(fish);
+                let mut totals = vec![total(&today, 0)?];
+                for day in 1..=last {
+                    today = model
+                        .step(&today, |n| {
+                            Binomial::new(n, model.spawn)
+                                .expect("the chance was checked in Model::new")
+                                .sample(&mut rng)
+                        })
+                        .ok_or_else(|| overflow(day))?;
+                    totals.push(total(&today, day)?);
+                }
+                Ok(totals)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let expected = self.expected(fish, last);
+        Ok(days
+            .map(|day| {
+                let mut totals = runs.iter().map(|r| r[day as usize]).collect::<Vec<_>>();
+                totals.sort_unstable();
+                let mean = totals.iter().map(|&t| t as f64).sum::<f64>() / trials as f64;
+                let variance = match trials {
+                    1 => 0.0,
+                    _ => {
+                        totals
+                            .iter()
+                            .map(|&t| (t as f64 - mean).powi(2))
+                            .sum::<f64>()
+                            / (trials - 1) as f64
+                    }
+                };
+                // nearest rank
+                let percentile = |p: usize| totals[((p * trials).div_ceil(100)).max(1) - 1];
+                DayStats {
+                    day,
+                    expected: expected[day as usize],
+                    mean,
+                    variance,
+                    p5: percentile(5),
+                    median: percentile(50),
+                    p95: percentile(95),
+                }
+            })
+            .collect())
+    }
+}
+
+fn total(fish: &[u64], day: u64) -> Result<u64> {
+    fish.iter()
+        .try_fold(0u64, |sum, &n| sum.checked_add(n))
+        .ok_or_else(|| overflow(day))
+}
+
+fn overflow(day: u64) -> anyhow::Error {
+    anyhow!(
+        "the population is too large to count in a u64 on day {}",
+        day
+    )
+}
@@ -0,0 +1,134 @@
+// What moving one crab a given distance costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cost {
+    // a unit of fuel per step
+    Linear,
+    // one more for each step than the last
+    Triangular,
+    // the distance to the given power, for any exponent of at least 1
+    Power(u32),
+}
+
+impl Cost {
+    // saturating, so distances too far to pay for come out as u128::MAX
+    pub fn of(&self, distance: u128) -> u128 {
+        match self {
+            Cost::Linear => distance,
+            Cost::Triangular => match distance % 2 {
+                0 => (distance / 2).saturating_mul(distance + 1),
+                _ => distance.saturating_mul(distance.div_ceil(2)),
+            },
+            Cost::Power(k) => distance.saturating_pow(*k),
+        }
+    }
+
+    // the natural log of the cost, which keeps ordering costs too large to hold
+    fn ln(&self, distance: u128) -> f64 {
+        let d = distance as f64;
+        match self {
+            Cost::Linear => d.ln(),
+            Cost::Triangular => d.ln() + (d + 1.0).ln() - 2f64.ln(),
+            Cost::Power(k) => *k as f64 * d.ln(),
+        }
+    }
+}
+
+pub fn total_cost(positions: &[isize], dest: isize, cost: Cost) -> u128 {
+    positions
+        .iter()
+        .map(|&p| cost.of((p as i128 - dest as i128).unsigned_abs()))
+        .fold(0, u128::saturating_add)
+}
+
+// the natural log of the total cost, summed as exponentials scaled by the largest
+fn ln_total_cost(positions: &[isize], dest: i128, cost: Cost) -> f64 {
+    let ln = |p: isize| cost.ln((p as i128 - dest).unsigned_abs());
+    let top = positions
+        .iter()
+        .map(|&p| ln(p))
+        .fold(f64::NEG_INFINITY, f64::max);
+    if top == f64::NEG_INFINITY {
+        return top;
+    }
+    top + positions
+        .iter()
+        .map(|&p| (ln(p) - top).exp())
+        .sum::<f64>()
+        .ln()
+}
+
+/*
+    The position the crabs can line up on for the least fuel, and that fuel.
+
+    Linear costs are cheapest at the median. Triangular costs are within half
+    a step of the mean, as their total's slope is the sum of the distances
+    plus half the number of crabs on one side less the other, so only the
+    positions around it are tried. Anything else convex is ternary searched.
+*/
+pub fn cheapest(positions: &[isize], cost: Cost) -> Option<(isize, u128)> {
+    if positions.is_empty() {
+        return None;
+    }
+    let dest = match cost {
+        Cost::Linear => median(positions),
+        Cost::Triangular => {
+            let (min, max) = span(positions);
+            let mean = mean(positions);
+            (mean - 1..=mean + 1)
+                .map(|p| p.clamp(min as i128, max as i128) as isize)
+                .min_by_key(|&p| total_cost(positions, p, cost))
+                .unwrap()
+        }
+        Cost::Power(_) => ternary_search(positions, cost),
+    };
+    Some((dest, total_cost(positions, dest, cost)))
+}
+
+fn median(positions: &[isize]) -> isize {
+    let mut positions = positions.to_vec();
+    let middle = (positions.len() - 1) / 2;
+    *positions.select_nth_unstable(middle).1
+}
+
+// rounded down
+fn mean(positions: &[isize]) -> i128 {
+    let sum = positions.iter().map(|&p| p as i128).sum::<i128>();
+    sum.div_euclid(positions.len() as i128)
+}
+
+fn span(positions: &[isize]) -> (isize, isize) {
+    (
+        *positions.iter().min().unwrap(),
+        *positions.iter().max().unwrap(),
+    )
+}
+
+/*
+    For costs that are convex in the distance, which makes the total convex too.
+    Totals saturate far from the cheapest position, and two saturated probes
+    say nothing about which side it is on, so those are compared by the logs
+    of their totals instead.
+*/
+fn ternary_search(positions: &[isize], cost: Cost) -> isize {
+    // in i128 so the span of the crabs can't overflow
+    let total = |p: i128| total_cost(positions, p as isize, cost);
+    let (min, max) = span(positions);
+    let (mut lo, mut hi) = (min as i128, max as i128);
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let (m1, m2) = (lo + third, hi - third);
+        let order = match (total(m1), total(m2)) {
+            (u128::MAX, u128::MAX) => ln_total_cost(positions, m1, cost)
+                .partial_cmp(&ln_total_cost(positions, m2, cost))
+                .unwrap_or(std::cmp::Ordering::Equal),
+            (a, b) => a.cmp(&b),
+        };
+        match order {
+            std::cmp::Ordering::Less => hi = m2 - 1,
+            std::cmp::Ordering::Greater => lo = m1 + 1,
+            // the cheapest lies between the two
+            std::cmp::Ordering::Equal => (lo, hi) = (m1, m2),
+        }
+    }
+    (lo..=hi).min_by_key(|&p| total(p)).unwrap() as isize
+}
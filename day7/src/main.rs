@@ -1,38 +1,167 @@
-fn main() {
+mod align;
+
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use align::{cheapest, Cost};
+
+/*
+    Usage: day7 [--power <k>]
+           day7 --bench <crabs> <range>
+
+    `--power` also lines the crabs up for a cost of the distance to the k-th
+    power. The bench mode spreads that many crabs over 0..range and times
+    both parts.
+*/
+fn main() -> Result<()> {
     // let input = "16,1,2,0,4,2,7,1,2,14";
     let input = include_str!("input");
 
-    let positions = input
+    let mut power = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--power" => power = Some(value()?.parse()?),
+            "--bench" => {
+                let crabs = value()?.parse()?;
+                let range = value()?.parse()?;
+                return benchmark(crabs, range);
+            }
+            _ => bail!("unknown argument `{}`", arg),
+        }
+    }
+
+    let positions = parse_positions(input)?;
+    let calc = |cost| {
+        cheapest(&positions, cost)
+            .ok_or_else(|| anyhow!("there are no crabs"))
+            .map(|(_, fuel)| fuel)
+    };
+
+    println!("Part 1: {}", calc(Cost::Linear)?);
+    println!("Part 2: {}", calc(Cost::Triangular)?);
+    if let Some(k) = power {
+        if k == 0 {
+            bail!("the power must be at least 1 for the cost to be convex");
+        }
+        println!("Power {}: {}", k, calc(Cost::Power(k))?);
+    }
+    Ok(())
+}
+
+fn parse_positions(input: &str) -> Result<Vec<isize>> {
+    Ok(input
+        .trim()
         .split(',')
-        .map(|c| c.parse::<isize>().unwrap())
+        .map(|c| c.trim().parse::<isize>())
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn benchmark(crabs: usize, range: isize) -> Result<()> {
+    if crabs == 0 {
+        bail!("the bench needs at least one crab");
+    }
+    if range < 1 {
+        bail!("the crabs need a range of at least 1, got {}", range);
+    }
+    let mut rng = StdRng::seed_from_u64(7);
+    let positions = (0..crabs)
+        .map(|_| rng.gen_range(0..range))
         .collect::<Vec<_>>();
+    println!("Generated {} crabs", positions.len());
+
+    for cost in [Cost::Linear, Cost::Triangular, Cost::Power(2)] {
+        let start = Instant::now();
+        let (dest, fuel) = cheapest(&positions, cost).expect("there is at least one crab");
+        println!("{:?}: {} at {} in {:?}", cost, fuel, dest, start.elapsed());
+    }
+    Ok(())
+}
 
-    let max = positions.iter().max().unwrap();
-    let min = positions.iter().min().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use align::total_cost;
 
-    let calc = |triangle| {
-        (*min..*max)
-            .into_iter()
-            .map(|p| calc_total_fuel_cost(&positions, &p, triangle))
+    // every position from the first crab to the last, both included
+    fn brute_force(positions: &[isize], cost: Cost) -> u128 {
+        let min = *positions.iter().min().unwrap();
+        let max = *positions.iter().max().unwrap();
+        (min..=max)
+            .map(|p| total_cost(positions, p, cost))
             .min()
             .unwrap()
-    };
+    }
 
-    println!("Part 1: {}", calc(false));
-    println!("Part 2: {}", calc(true));
-}
+    #[test]
+    fn example() {
+        let positions = parse_positions("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(Some((2, 37)), cheapest(&positions, Cost::Linear));
+        assert_eq!(Some((5, 168)), cheapest(&positions, Cost::Triangular));
+        assert_eq!(None, cheapest(&[], Cost::Linear));
+    }
 
-fn calc_total_fuel_cost(positions: &[isize], dest: &isize, use_triangle: bool) -> isize {
-    positions
-        .iter()
-        .map(|p| (p - dest).abs())
-        .map(|p| match use_triangle {
-            false => p,
-            true => triangle(p),
-        })
-        .sum()
-}
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for round in 0..300 {
+            let crabs = rng.gen_range(1..20);
+            let spread = rng.gen_range(1..200);
+            let positions = (0..crabs)
+                .map(|_| rng.gen_range(-spread..spread))
+                .collect::<Vec<_>>();
+            for cost in [
+                Cost::Linear,
+                Cost::Triangular,
+                Cost::Power(1),
+                Cost::Power(3),
+            ] {
+                let (dest, fuel) = cheapest(&positions, cost).unwrap();
+                assert_eq!(total_cost(&positions, dest, cost), fuel);
+                assert_eq!(
+                    brute_force(&positions, cost),
+                    fuel,
+                    "round {}, {:?} for {:?}",
+                    round,
+                    cost,
+                    positions
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn huge_ranges() {
+        // the best spot is the far end, which the exclusive range used to skip
+        let positions = [0, 1_000_000_000_000, 1_000_000_000_000];
+        assert_eq!(
+            Some((1_000_000_000_000, 1_000_000_000_000)),
+            cheapest(&positions, Cost::Linear)
+        );
+        let (_, fuel) = cheapest(&positions, Cost::Triangular).unwrap();
+        assert!(fuel > u64::MAX as u128);
+        // too much fuel to count anywhere, but no overflow finding that out
+        let (_, fuel) = cheapest(&[isize::MIN, isize::MAX, isize::MAX], Cost::Power(4)).unwrap();
+        assert_eq!(u128::MAX, fuel);
+    }
 
-fn triangle(x: isize) -> isize {
-    (x * (x + 1)) / 2
+    #[test]
+    fn saturated_probes() {
+        // the outlier saturates the total at both first probes, though the
+        // cheapest spot, near the crowd, can still be paid for
+        let mut positions = vec![0; 100];
+        positions.push(9_000_000_000_000_000_000);
+        let (dest, fuel) = cheapest(&positions, Cost::Power(2)).unwrap();
+        assert_eq!(89_108_910_891_089_109, dest);
+        assert!(fuel < u128::MAX);
+        for p in [dest - 1, dest + 1] {
+            assert!(total_cost(&positions, p, Cost::Power(2)) > fuel);
+        }
+    }
 }